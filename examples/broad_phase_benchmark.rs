@@ -0,0 +1,72 @@
+use std::time::Instant;
+
+use physics_engine::types::collider::Collider;
+use physics_engine::types::rigid_body::RigidBody;
+use physics_engine::types::vector_2d::Vector2D;
+use physics_engine::types::world::World;
+use physics_engine::utilities::broad_phase::BroadPhase;
+use physics_engine::utilities::sweep_and_prune::SweepAxis;
+
+// Run with `cargo run --release --example broad_phase_benchmark`.
+
+// Granular scene: a few thousand small circles dropped into a box. Every circle moves each step,
+// which suits the grid.
+fn granular_scene(broad_phase: BroadPhase) -> World {
+    let mut world = World::new().with_broad_phase(broad_phase);
+    let walls = [
+        (Vector2D::new(0.0, -1.0), 52.0, 1.0),
+        (Vector2D::new(-51.0, 50.0), 1.0, 50.0),
+        (Vector2D::new(51.0, 50.0), 1.0, 50.0),
+    ];
+    for (center, half_width, half_height) in walls {
+        world.insert(RigidBody::new(0.0, center, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(half_width, half_height)));
+    }
+
+    for row in 0..40 {
+        for column in 0..100 {
+            let offset = if row % 2 == 0 { 0.0 } else { 0.25 };
+            let position = Vector2D::new(-49.5 + column as f32 + offset, 0.5 + row as f32 * 1.1);
+            world.insert(RigidBody::from_collider(Collider::circle(0.4), 1.0, position));
+        }
+    }
+    world
+}
+
+// Small circles resting on static platforms hundreds of times their size. The grid has to test
+// the platforms against every body, while the tree only revisits the pairs of bodies that moved
+// far enough to be reinserted.
+fn mixed_scene(broad_phase: BroadPhase) -> World {
+    let mut world = World::new().with_broad_phase(broad_phase);
+    for level in 0..10 {
+        let center = Vector2D::new(0.0, level as f32 * 12.0 - 1.0);
+        world.insert(RigidBody::new(0.0, center, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(200.0, 1.0)));
+        for column in 0..200 {
+            let position = Vector2D::new(-199.0 + column as f32 * 2.0, level as f32 * 12.0 + 0.5);
+            world.insert(RigidBody::from_collider(Collider::circle(0.4), 1.0, position));
+        }
+    }
+    world
+}
+
+fn time_steps(name: &str, mut world: World, steps: u32) {
+    let start = Instant::now();
+    for _ in 0..steps {
+        world.step(1.0 / 60.0);
+    }
+    let elapsed = start.elapsed();
+    println!("{:<14} {} bodies, {} steps: {:>8.2?} ({:.2?} per step)",
+             name, world.len(), steps, elapsed, elapsed / steps);
+}
+
+fn main() {
+    let steps = 20;
+    for (scene_name, scene) in [("granular", granular_scene as fn(BroadPhase) -> World), ("mixed sizes", mixed_scene)] {
+        println!("{}:", scene_name);
+        time_steps("brute force", scene(BroadPhase::BruteForce), steps);
+        time_steps("spatial hash", scene(BroadPhase::spatial_hash(1.0)), steps);
+        time_steps("sweep & prune", scene(BroadPhase::sweep_and_prune(SweepAxis::GreatestVariance)), steps);
+        time_steps("dynamic tree", scene(BroadPhase::dynamic_tree(0.1)), steps);
+    }
+}
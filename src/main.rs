@@ -1,51 +1,49 @@
-use crate::types::rigid_body::RigidBody;
-use crate::types::vector_2d::Vector2D;
-use crate::utilities::world_functions::physics_step;
-use crate::utilities::plotter::plot_trajectories;
-
-mod types;
-mod utilities;
+use physics_engine::types::rigid_body::RigidBody;
+use physics_engine::types::stepper::Stepper;
+use physics_engine::types::vector_2d::Vector2D;
+use physics_engine::types::world::World;
+use physics_engine::utilities::plotter::plot_trajectories;
 
 fn main() -> Result<(), Box<dyn std::error::Error>>
 {
-    let mut bodies: Vec<&mut RigidBody> = vec![];
-    let mut body_1 = RigidBody::new(
+    let mut world = World::new();
+    let body_1 = world.insert(RigidBody::new(
         2.0,
         Vector2D::new(13.0, -5.0),
         Vector2D::new(1.0, 0.2),
         Vector2D::new(0.0, 0.0)
-    );
-    bodies.push(&mut body_1);
+    ));
 
-    let mut body_2 = RigidBody::new(
+    let body_2 = world.insert(RigidBody::new(
         15.0,
         Vector2D::new(4.0, 2.0),
         Vector2D::new(0.0, 0.2),
         Vector2D::new(0.0, 0.0)
-    );
-    bodies.push(&mut body_2);
+    ));
 
-    let mut body_3 = RigidBody::new(
+    let body_3 = world.insert(RigidBody::new(
         0.1,
         Vector2D::new(50.0, -60.0),
         Vector2D::new(15.0, -23.2),
         Vector2D::new(0.0, 0.0)
-    );
-    bodies.push(&mut body_3);
+    ));
 
+    let tracked = [body_1, body_2, body_3];
     let dt = 1.0 / 60.0;
-    let mut body_positions: Vec<Vec<(f32, f32)>> = vec![Vec::new(); bodies.len()
-    ];
+    let mut stepper = Stepper::new(dt);
+    let mut body_positions: Vec<Vec<(f32, f32)>> = vec![Vec::new(); tracked.len()];
 
     for _ in 0..300 {
-        physics_step(&mut bodies, dt);
+        stepper.advance(&mut world, dt);
 
-        for (i, body) in bodies.iter().enumerate() {
-            body_positions[i].push((body.position.x, body.position.y));
+        for (i, handle) in tracked.iter().enumerate() {
+            if let Some(position) = stepper.interpolated_position(&world, *handle) {
+                body_positions[i].push((position.x, position.y));
+            }
         }
-        // for (i, body) in bodies.iter_mut().enumerate() {
+        // for (handle, body) in world.iter() {
         //     println!("Frame: {}", frame);
-        //     println!("Body {i}: \n{:#?}", body);
+        //     println!("Body {:?}: \n{:#?}", handle, body);
         //     println!();
         // }
     }
@@ -15,4 +15,76 @@ impl BoundingBox {
             half_height,
         }
     }
-}
\ No newline at end of file
+
+    pub fn from_min_max(min: Vector2D, max: Vector2D) -> Self {
+        BoundingBox {
+            center: (min + max) * 0.5,
+            half_width: (max.x - min.x) * 0.5,
+            half_height: (max.y - min.y) * 0.5,
+        }
+    }
+
+    pub fn min(&self) -> Vector2D {
+        Vector2D::new(self.center.x - self.half_width, self.center.y - self.half_height)
+    }
+
+    pub fn max(&self) -> Vector2D {
+        Vector2D::new(self.center.x + self.half_width, self.center.y + self.half_height)
+    }
+
+    pub fn contains_point(&self, point: Vector2D) -> bool {
+        (point.x - self.center.x).abs() <= self.half_width &&
+            (point.y - self.center.y).abs() <= self.half_height
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        let min = self.min();
+        let max = self.max();
+        let other_min = other.min();
+        let other_max = other.max();
+        BoundingBox::from_min_max(
+            Vector2D::new(f32::min(min.x, other_min.x), f32::min(min.y, other_min.y)),
+            Vector2D::new(f32::max(max.x, other_max.x), f32::max(max.y, other_max.y)),
+        )
+    }
+
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        let (min, max) = (self.min(), self.max());
+        let (other_min, other_max) = (other.min(), other.max());
+        min.x <= other_min.x && min.y <= other_min.y && other_max.x <= max.x && other_max.y <= max.y
+    }
+
+    // The 2D stand-in for surface area when comparing how much boxes cost to test against.
+    pub fn perimeter(&self) -> f32 {
+        4.0 * (self.half_width + self.half_height)
+    }
+
+    pub fn expanded(&self, margin: f32) -> BoundingBox {
+        BoundingBox::new(self.center, self.half_width + margin, self.half_height + margin)
+    }
+
+    // Intersects the segment `origin + delta * t` for t in [0, max_fraction] with the box and
+    // returns the t where it enters. A segment starting inside the box hits at 0.
+    pub fn raycast(&self, origin: Vector2D, delta: Vector2D, max_fraction: f32) -> Option<f32> {
+        let (min, max) = (self.min(), self.max());
+        let mut t_min: f32 = 0.0;
+        let mut t_max = max_fraction;
+
+        for (start, direction, low, high) in [(origin.x, delta.x, min.x, max.x), (origin.y, delta.y, min.y, max.y)] {
+            if direction.abs() < f32::EPSILON {
+                if start < low || start > high {
+                    return None;
+                }
+                continue;
+            }
+            let inverse = 1.0 / direction;
+            let (t_1, t_2) = ((low - start) * inverse, (high - start) * inverse);
+            t_min = t_min.max(t_1.min(t_2));
+            t_max = t_max.min(t_1.max(t_2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::segment::Segment;
+use crate::types::vector_2d::Vector2D;
+
+// Every point within `radius` of the segment between `start` and `end`: a rectangle with two
+// half circles on its ends.
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule {
+    pub start: Vector2D,
+    pub end: Vector2D,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub fn new(start: Vector2D, end: Vector2D, radius: f32) -> Self {
+        Capsule { start, end, radius }
+    }
+
+    // The line the capsule is rounded around.
+    pub fn segment(&self) -> Segment {
+        Segment::new(self.start, self.end)
+    }
+
+    pub fn center(&self) -> Vector2D {
+        (self.start + self.end) * 0.5
+    }
+
+    pub fn translated(&self, offset: Vector2D) -> Capsule {
+        Capsule::new(self.start + offset, self.end + offset, self.radius)
+    }
+
+    // Rotates about the local origin, then moves to `position`.
+    pub fn transformed(&self, position: Vector2D, angle: f32) -> Capsule {
+        let segment = self.segment().transformed(position, angle);
+        Capsule::new(segment.start, segment.end, self.radius)
+    }
+
+    // Same core segment with a larger radius.
+    pub fn inflated(&self, margin: f32) -> Capsule {
+        Capsule::new(self.start, self.end, self.radius + margin)
+    }
+
+    pub fn aabb(&self) -> BoundingBox {
+        self.segment().aabb().expanded(self.radius)
+    }
+
+    // Closest point on the capsule's surface, or `point` itself when it is inside.
+    pub fn closest_point(&self, point: Vector2D) -> Vector2D {
+        let core = self.segment().closest_point(point);
+        let offset = point - core;
+        let distance = offset.length();
+        if distance <= self.radius {
+            return point;
+        }
+        core + offset * (self.radius / distance)
+    }
+
+    pub fn contains_point(&self, point: Vector2D) -> bool {
+        let offset = point - self.segment().closest_point(point);
+        offset.dot(&offset) <= self.radius * self.radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_point() {
+        let capsule = Capsule::new(Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 2.0), 0.5);
+
+        let side = capsule.closest_point(Vector2D::new(3.0, 1.0));
+        assert!((side.x - 0.5).abs() < 1e-6 && (side.y - 1.0).abs() < 1e-6);
+        let cap = capsule.closest_point(Vector2D::new(0.0, 5.0));
+        assert!((cap.y - 2.5).abs() < 1e-6);
+        let inside = capsule.closest_point(Vector2D::new(0.2, 0.3));
+        assert_eq!(inside.x, 0.2);
+    }
+
+    #[test]
+    fn test_contains_point_and_aabb() {
+        let capsule = Capsule::new(Vector2D::new(-1.0, 0.0), Vector2D::new(1.0, 0.0), 0.5);
+
+        assert!(capsule.contains_point(Vector2D::new(1.3, 0.3)));
+        assert!(!capsule.contains_point(Vector2D::new(1.4, 0.4)));
+        let aabb = capsule.aabb();
+        assert_eq!(aabb.min().x, -1.5);
+        assert_eq!(aabb.max().y, 0.5);
+    }
+}
@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use crate::types::bounding_box::BoundingBox;
+use crate::types::segment::Segment;
+use crate::types::vector_2d::Vector2D;
+use crate::utilities::collisions::bounding_box_collision;
+
+// Points closer than this to the previous one are dropped, since the edge between them would
+// have no direction to take a normal from.
+pub const MIN_EDGE_LENGTH: f32 = 1e-6;
+
+// Polyline of one-sided edges for static terrain. Each edge only collides on its right side when
+// walking from one vertex to the next, so a counter-clockwise loop faces outwards and an open
+// ground line has to run from right to left to face up.
+//
+// The vertices and a bounding volume hierarchy over the edges are shared between copies and kept
+// in the chain's own frame; moving the chain only changes its pose. That keeps turning a long
+// chain into world space cheap, and lets the narrow phase visit only the edges near a shape.
+#[derive(Debug, Clone)]
+pub struct Chain {
+    vertices: Arc<[Vector2D]>,
+    looped: bool,
+    // `levels[0]` holds the AABB of every edge, and each level above joins pairs of the one below
+    // until a single box is left. Edges along a polyline are neighbours in space too, so pairing
+    // them by index gives tight boxes.
+    levels: Arc<[Vec<BoundingBox>]>,
+    position: Vector2D,
+    angle: f32,
+}
+
+// One edge of a chain together with its neighbours' far vertices. The ghost vertices tell the
+// narrow phase whether a corner is really exposed, which stops shapes catching on the joint
+// between two edges that are in line.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainEdge {
+    pub segment: Segment,
+    pub normal: Vector2D,
+    pub ghost_before: Option<Vector2D>,
+    pub ghost_after: Option<Vector2D>,
+}
+
+impl Chain {
+    // Needs at least two distinct points.
+    pub fn open(points: &[Vector2D]) -> Option<Self> {
+        let vertices = without_repeats(points);
+        if vertices.len() < 2 {
+            return None;
+        }
+        Some(Chain::build(vertices, false))
+    }
+
+    // Closes the polyline back onto its first point. Needs at least three distinct points.
+    pub fn looped(points: &[Vector2D]) -> Option<Self> {
+        let mut vertices = without_repeats(points);
+        if vertices.len() > 1 && (vertices[vertices.len() - 1] - vertices[0]).length() < MIN_EDGE_LENGTH {
+            vertices.pop();
+        }
+        if vertices.len() < 3 {
+            return None;
+        }
+        Some(Chain::build(vertices, true))
+    }
+
+    fn build(vertices: Vec<Vector2D>, looped: bool) -> Self {
+        let mut chain = Chain {
+            vertices: vertices.into(),
+            looped,
+            levels: Arc::new([]),
+            position: Vector2D::new(0.0, 0.0),
+            angle: 0.0,
+        };
+        let mut levels = vec![chain.edges().map(|edge| edge.segment.aabb()).collect::<Vec<_>>()];
+        while levels[levels.len() - 1].len() > 1 {
+            let parents = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| pair.iter().skip(1).fold(pair[0], |union, aabb| union.union(aabb)))
+                .collect();
+            levels.push(parents);
+        }
+        chain.levels = levels.into();
+        chain
+    }
+
+    // Vertices in world space.
+    pub fn vertices(&self) -> impl Iterator<Item = Vector2D> + '_ {
+        self.vertices.iter().map(|vertex| self.to_world(*vertex))
+    }
+
+    pub fn is_looped(&self) -> bool {
+        self.looped
+    }
+
+    pub fn edge_count(&self) -> usize {
+        if self.looped { self.vertices.len() } else { self.vertices.len() - 1 }
+    }
+
+    pub fn edge(&self, index: usize) -> ChainEdge {
+        let count = self.vertices.len();
+        let vertex = |i: isize| -> Option<Vector2D> {
+            let local = if self.looped {
+                Some(self.vertices[i.rem_euclid(count as isize) as usize])
+            }
+            else if i >= 0 && (i as usize) < count {
+                Some(self.vertices[i as usize])
+            }
+            else {
+                None
+            };
+            local.map(|vertex| self.to_world(vertex))
+        };
+
+        let index = index as isize;
+        let start = vertex(index).expect("edge index in range");
+        let end = vertex(index + 1).expect("edge index in range");
+        let direction = end - start;
+        ChainEdge {
+            segment: Segment::new(start, end),
+            normal: Vector2D::new(direction.y, -direction.x) / direction.length(),
+            ghost_before: vertex(index - 1),
+            ghost_after: vertex(index + 2),
+        }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = ChainEdge> + '_ {
+        (0..self.edge_count()).map(|index| self.edge(index))
+    }
+
+    pub fn edge_aabb(&self, index: usize) -> BoundingBox {
+        self.edge(index).segment.aabb()
+    }
+
+    // Indices of the edges whose AABB may overlap `aabb`, in increasing order. Walks the
+    // hierarchy, so only the edges near `aabb` are looked at.
+    pub fn edges_near(&self, aabb: &BoundingBox) -> Vec<usize> {
+        let local = self.to_local_aabb(aabb);
+        let mut found = Vec::new();
+        let mut stack = vec![(self.levels.len() - 1, 0)];
+        while let Some((level, index)) = stack.pop() {
+            if !bounding_box_collision(self.levels[level][index], local) {
+                continue;
+            }
+            if level == 0 {
+                found.push(index);
+                continue;
+            }
+            let below = &self.levels[level - 1];
+            stack.extend([2 * index + 1, 2 * index].into_iter()
+                .filter(|child| *child < below.len())
+                .map(|child| (level - 1, child)));
+        }
+        found
+    }
+
+    pub fn aabb(&self) -> BoundingBox {
+        let root = self.levels[self.levels.len() - 1][0];
+        if self.angle == 0.0 {
+            return BoundingBox::new(root.center + self.position, root.half_width, root.half_height);
+        }
+        let first = self.to_world(self.vertices[0]);
+        let (min, max) = self.vertices().fold((first, first), |(min, max), vertex| (
+            Vector2D::new(min.x.min(vertex.x), min.y.min(vertex.y)),
+            Vector2D::new(max.x.max(vertex.x), max.y.max(vertex.y)),
+        ));
+        BoundingBox::from_min_max(min, max)
+    }
+
+    pub fn translated(&self, offset: Vector2D) -> Chain {
+        Chain {
+            position: self.position + offset,
+            ..self.clone()
+        }
+    }
+
+    // Rotates about the local origin, then moves to `position`.
+    pub fn transformed(&self, position: Vector2D, angle: f32) -> Chain {
+        Chain {
+            position: position + self.position.rotated(angle),
+            angle: self.angle + angle,
+            ..self.clone()
+        }
+    }
+
+    fn to_world(&self, vertex: Vector2D) -> Vector2D {
+        self.position + vertex.rotated(self.angle)
+    }
+
+    // Box around `aabb` as seen from the chain's own frame.
+    fn to_local_aabb(&self, aabb: &BoundingBox) -> BoundingBox {
+        let center = (aabb.center - self.position).rotated(-self.angle);
+        if self.angle == 0.0 {
+            return BoundingBox::new(center, aabb.half_width, aabb.half_height);
+        }
+        let (sin, cos) = self.angle.sin_cos();
+        BoundingBox::new(
+            center,
+            cos.abs() * aabb.half_width + sin.abs() * aabb.half_height,
+            sin.abs() * aabb.half_width + cos.abs() * aabb.half_height,
+        )
+    }
+}
+
+fn without_repeats(points: &[Vector2D]) -> Vec<Vector2D> {
+    let mut vertices: Vec<Vector2D> = Vec::with_capacity(points.len());
+    for point in points {
+        if vertices.last().is_none_or(|last| (*point - *last).length() >= MIN_EDGE_LENGTH) {
+            vertices.push(*point);
+        }
+    }
+    vertices
+}
+
+impl ChainEdge {
+    // True when the corner at `start` sticks out, so the previous edge does not cover it.
+    pub fn start_is_convex(&self) -> bool {
+        self.ghost_before.is_none_or(|ghost| {
+            (self.segment.start - ghost).cross(&(self.segment.end - self.segment.start)) > f32::EPSILON
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps() -> Vec<Vector2D> {
+        vec![
+            Vector2D::new(4.0, 0.0),
+            Vector2D::new(2.0, 0.0),
+            Vector2D::new(0.0, 1.0),
+            Vector2D::new(-2.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn test_open_chain_edges_and_ghosts() {
+        let chain = Chain::open(&steps()).unwrap();
+        assert_eq!(chain.edge_count(), 3);
+
+        let first = chain.edge(0);
+        assert!(first.ghost_before.is_none());
+        assert_eq!(first.ghost_after.unwrap().x, 0.0);
+        // Running right to left, the right hand side is up.
+        assert!((first.normal.y - 1.0).abs() < 1e-6);
+
+        let last = chain.edge(2);
+        assert_eq!(last.ghost_before.unwrap().x, 2.0);
+        assert!(last.ghost_after.is_none());
+    }
+
+    #[test]
+    fn test_looped_chain_wraps_around() {
+        let chain = Chain::looped(&steps()).unwrap();
+        assert_eq!(chain.edge_count(), 4);
+
+        let closing = chain.edge(3);
+        assert_eq!(closing.segment.start.x, -2.0);
+        assert_eq!(closing.segment.end.x, 4.0);
+        assert_eq!(closing.ghost_before.unwrap().x, 0.0);
+        assert_eq!(closing.ghost_after.unwrap().x, 2.0);
+    }
+
+    #[test]
+    fn test_convex_corners() {
+        let chain = Chain::open(&steps()).unwrap();
+
+        // Stepping up from the lower ground is a concave corner, the top of the step convex.
+        assert!(chain.edge(0).start_is_convex());
+        assert!(!chain.edge(1).start_is_convex());
+        assert!(chain.edge(2).start_is_convex());
+
+        let flat = Chain::open(&[Vector2D::new(1.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(-1.0, 0.0)]).unwrap();
+        assert!(!flat.edge(1).start_is_convex());
+    }
+
+    #[test]
+    fn test_too_few_points() {
+        assert!(Chain::open(&[Vector2D::new(0.0, 0.0)]).is_none());
+        assert!(Chain::looped(&[Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0)]).is_none());
+    }
+
+    #[test]
+    fn test_edge_aabb() {
+        let chain = Chain::open(&steps()).unwrap();
+        let aabb = chain.edge_aabb(1);
+        assert_eq!(aabb.min().x, 0.0);
+        assert_eq!(aabb.max().y, 1.0);
+        assert_eq!(chain.aabb().min().x, -2.0);
+    }
+
+    #[test]
+    fn test_repeated_points_are_dropped() {
+        let mut points = steps();
+        points.insert(1, points[0]);
+        points.push(points[0]);
+        let chain = Chain::looped(&points).unwrap();
+        assert_eq!(chain.edge_count(), 4);
+        assert!(chain.edges().all(|edge| edge.normal.x.is_finite() && edge.normal.y.is_finite()));
+
+        let same = Vector2D::new(1.0, 1.0);
+        assert!(Chain::open(&[same, same]).is_none());
+        assert!(Chain::looped(&[same, Vector2D::new(2.0, 1.0), same]).is_none());
+    }
+
+    #[test]
+    fn test_edges_near_matches_every_edge_check() {
+        let points: Vec<Vector2D> = (0..300)
+            .map(|i| Vector2D::new(-(i as f32) * 0.5, f32::sin(i as f32 * 0.3)))
+            .collect();
+        let chain = Chain::open(&points).unwrap().transformed(Vector2D::new(3.0, -2.0), 0.4);
+
+        for center in [Vector2D::new(-20.0, -10.0), Vector2D::new(-60.0, -30.0), Vector2D::new(500.0, 0.0)] {
+            let aabb = BoundingBox::new(center, 1.5, 0.75);
+            let near = chain.edges_near(&aabb);
+            let expected: Vec<usize> = (0..chain.edge_count())
+                .filter(|&index| bounding_box_collision(chain.edge_aabb(index), aabb))
+                .collect();
+            assert!(expected.iter().all(|index| near.contains(index)), "Missed edges near {:?}", center);
+            assert!(near.len() <= expected.len() + 4, "{} edges near {:?}", near.len(), center);
+        }
+    }
+
+    #[test]
+    fn test_transformed_chain_shares_local_data() {
+        let chain = Chain::open(&steps()).unwrap();
+        let moved = chain.transformed(Vector2D::new(1.0, 2.0), std::f32::consts::FRAC_PI_2);
+        assert!(Arc::ptr_eq(&chain.vertices, &moved.vertices));
+
+        let first = moved.edge(0);
+        assert!((first.segment.start.x - 1.0).abs() < 1e-5);
+        assert!((first.segment.start.y - 6.0).abs() < 1e-5);
+        assert!((first.normal.x + 1.0).abs() < 1e-5);
+        assert_eq!(moved.vertices().count(), 4);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::capsule::Capsule;
+use crate::types::chain::Chain;
+use crate::types::circle::Circle;
+use crate::types::convex_shape::ConvexShape;
+use crate::types::mass_properties::MassProperties;
+use crate::types::polygon::Polygon;
+use crate::types::segment::Segment;
+use crate::types::vector_2d::Vector2D;
+
+// Shapes are stored in the body's local coordinates, so `center` is an offset from the body
+// position. `world_shape` places them in the world. A box on a rotated body comes out as a
+// polygon, so it only keeps the cheaper box routines while its body is unrotated.
+#[derive(Debug, Clone)]
+pub enum Collider {
+    Circle(Circle),
+    Box(BoundingBox),
+    Polygon(Polygon),
+    Capsule(Capsule),
+    // Thin edge with no area and so no mass; meant for static geometry.
+    Segment(Segment),
+    // One-sided polyline for static terrain, see `Chain`. Massless like a segment.
+    Chain(Chain),
+    // Several shapes rigidly attached to the same body.
+    Compound(Vec<Collider>),
+}
+
+impl Collider {
+    pub fn circle(radius: f32) -> Self {
+        Collider::Circle(Circle::new(Vector2D::new(0.0, 0.0), radius))
+    }
+
+    pub fn rectangle(half_width: f32, half_height: f32) -> Self {
+        Collider::Box(BoundingBox::new(Vector2D::new(0.0, 0.0), half_width, half_height))
+    }
+
+    // Upright capsule, the usual character shape. `half_length` excludes the rounded ends.
+    pub fn capsule(half_length: f32, radius: f32) -> Self {
+        Collider::Capsule(Capsule::new(Vector2D::new(0.0, -half_length), Vector2D::new(0.0, half_length), radius))
+    }
+
+    pub fn segment(start: Vector2D, end: Vector2D) -> Self {
+        Collider::Segment(Segment::new(start, end))
+    }
+
+    // Open polyline of one-sided edges, see `Chain::open`.
+    pub fn chain(points: &[Vector2D]) -> Option<Self> {
+        Chain::open(points).map(Collider::Chain)
+    }
+
+    // Closed polyline of one-sided edges, see `Chain::looped`.
+    pub fn chain_loop(points: &[Vector2D]) -> Option<Self> {
+        Chain::looped(points).map(Collider::Chain)
+    }
+
+    // Convex hull of `points`, see `Polygon::new`.
+    pub fn polygon(points: &[Vector2D]) -> Option<Self> {
+        Polygon::new(points).map(Collider::Polygon)
+    }
+
+    // Compounds and chains are not convex as a whole; their parts are collided one by one instead.
+    pub fn as_convex(&self) -> Option<&dyn ConvexShape> {
+        match self {
+            Collider::Circle(circle) => Some(circle),
+            Collider::Box(bounding_box) => Some(bounding_box),
+            Collider::Polygon(polygon) => Some(polygon),
+            Collider::Capsule(capsule) => Some(capsule),
+            Collider::Segment(segment) => Some(segment),
+            Collider::Chain(_) | Collider::Compound(_) => None,
+        }
+    }
+
+    pub fn translated(&self, offset: Vector2D) -> Collider {
+        match self {
+            Collider::Circle(circle) => Collider::Circle(Circle::new(circle.center + offset, circle.radius)),
+            Collider::Box(bounding_box) => Collider::Box(BoundingBox::new(
+                bounding_box.center + offset,
+                bounding_box.half_width,
+                bounding_box.half_height,
+            )),
+            Collider::Polygon(polygon) => Collider::Polygon(polygon.translated(offset)),
+            Collider::Capsule(capsule) => Collider::Capsule(capsule.translated(offset)),
+            Collider::Segment(segment) => Collider::Segment(segment.translated(offset)),
+            Collider::Chain(chain) => Collider::Chain(chain.translated(offset)),
+            Collider::Compound(children) => {
+                Collider::Compound(children.iter().map(|child| child.translated(offset)).collect())
+            }
+        }
+    }
+
+    pub fn world_shape(&self, position: Vector2D, angle: f32) -> Collider {
+        match self {
+            Collider::Circle(circle) => Collider::Circle(Circle::new(
+                position + circle.center.rotated(angle),
+                circle.radius,
+            )),
+            Collider::Box(bounding_box) if angle == 0.0 => Collider::Box(BoundingBox::new(
+                position + bounding_box.center,
+                bounding_box.half_width,
+                bounding_box.half_height,
+            )),
+            Collider::Box(bounding_box) => Collider::Polygon(Polygon::from(*bounding_box).transformed(position, angle)),
+            Collider::Polygon(polygon) => Collider::Polygon(polygon.transformed(position, angle)),
+            Collider::Capsule(capsule) => Collider::Capsule(capsule.transformed(position, angle)),
+            Collider::Segment(segment) => Collider::Segment(segment.transformed(position, angle)),
+            Collider::Chain(chain) => Collider::Chain(chain.transformed(position, angle)),
+            Collider::Compound(children) => {
+                Collider::Compound(children.iter().map(|child| child.world_shape(position, angle)).collect())
+            }
+        }
+    }
+
+    pub fn aabb(&self, position: Vector2D, angle: f32) -> BoundingBox {
+        self.world_shape(position, angle).world_aabb()
+    }
+
+    // For shapes that are already in world space.
+    pub fn world_aabb(&self) -> BoundingBox {
+        match self {
+            Collider::Circle(circle) => BoundingBox::new(circle.center, circle.radius, circle.radius),
+            Collider::Box(bounding_box) => *bounding_box,
+            Collider::Polygon(polygon) => polygon.aabb(),
+            Collider::Capsule(capsule) => capsule.aabb(),
+            Collider::Segment(segment) => segment.aabb(),
+            Collider::Chain(chain) => chain.aabb(),
+            Collider::Compound(children) => children.iter()
+                .map(|child| child.world_aabb())
+                .reduce(|aabb_1, aabb_2| aabb_1.union(&aabb_2))
+                .unwrap_or(BoundingBox::new(Vector2D::new(0.0, 0.0), 0.0, 0.0)),
+        }
+    }
+
+    // For shapes that are already in world space. Segments and chains have no inside.
+    pub fn contains_point(&self, point: Vector2D) -> bool {
+        match self {
+            Collider::Circle(circle) => {
+                let offset = point - circle.center;
+                offset.dot(&offset) <= circle.radius * circle.radius
+            }
+            Collider::Box(bounding_box) => bounding_box.contains_point(point),
+            Collider::Polygon(polygon) => polygon.contains_point(point),
+            Collider::Capsule(capsule) => capsule.contains_point(point),
+            Collider::Segment(_) | Collider::Chain(_) => false,
+            Collider::Compound(children) => children.iter().any(|child| child.contains_point(point)),
+        }
+    }
+
+    pub fn mass_properties(&self, density: f32) -> MassProperties {
+        match self {
+            Collider::Circle(circle) => MassProperties::circle(circle, density),
+            Collider::Box(bounding_box) => MassProperties::rectangle(bounding_box, density),
+            Collider::Polygon(polygon) => MassProperties::polygon(polygon.vertices(), density),
+            Collider::Capsule(capsule) => MassProperties::capsule(capsule, density),
+            Collider::Segment(segment) => MassProperties::new(0.0, segment.midpoint(), 0.0),
+            Collider::Chain(chain) => MassProperties::new(0.0, chain.aabb().center, 0.0),
+            Collider::Compound(children) => MassProperties::combine(
+                &children.iter().map(|child| child.mass_properties(density)).collect::<Vec<_>>()
+            ),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_shape_circle_with_offset() {
+        let collider = Collider::Circle(Circle::new(Vector2D::new(1.0, 0.0), 0.5));
+        match collider.world_shape(Vector2D::new(2.0, 3.0), 0.0) {
+            Collider::Circle(circle) => {
+                assert_eq!(circle.center.x, 3.0);
+                assert_eq!(circle.center.y, 3.0);
+                assert_eq!(circle.radius, 0.5);
+            }
+            _ => panic!("Expected a circle"),
+        }
+    }
+
+    #[test]
+    fn test_world_shape_box() {
+        let collider = Collider::rectangle(2.0, 1.0);
+        match collider.world_shape(Vector2D::new(-1.0, 4.0), 0.0) {
+            Collider::Box(bounding_box) => {
+                assert_eq!(bounding_box.center.x, -1.0);
+                assert_eq!(bounding_box.center.y, 4.0);
+                assert_eq!(bounding_box.half_width, 2.0);
+                assert_eq!(bounding_box.half_height, 1.0);
+            }
+            _ => panic!("Expected a box"),
+        }
+    }
+
+    #[test]
+    fn test_aabb_circle() {
+        let collider = Collider::Circle(Circle::new(Vector2D::new(0.0, -1.0), 2.0));
+        let aabb = collider.aabb(Vector2D::new(5.0, 5.0), 0.0);
+        assert_eq!(aabb.min().x, 3.0);
+        assert_eq!(aabb.min().y, 2.0);
+        assert_eq!(aabb.max().x, 7.0);
+        assert_eq!(aabb.max().y, 6.0);
+    }
+
+    #[test]
+    fn test_aabb_box_with_offset() {
+        let collider = Collider::Box(BoundingBox::new(Vector2D::new(1.0, 1.0), 0.5, 0.25));
+        let aabb = collider.aabb(Vector2D::new(0.0, 0.0), 0.0);
+        assert_eq!(aabb.min().x, 0.5);
+        assert_eq!(aabb.min().y, 0.75);
+        assert_eq!(aabb.max().x, 1.5);
+        assert_eq!(aabb.max().y, 1.25);
+    }
+
+    #[test]
+    fn test_world_shape_rotates_offset() {
+        let collider = Collider::Circle(Circle::new(Vector2D::new(1.0, 0.0), 0.5));
+        match collider.world_shape(Vector2D::new(2.0, 3.0), std::f32::consts::FRAC_PI_2) {
+            Collider::Circle(circle) => {
+                assert!((circle.center.x - 2.0).abs() < 1e-6);
+                assert!((circle.center.y - 4.0).abs() < 1e-6);
+            }
+            _ => panic!("Expected a circle"),
+        }
+    }
+
+    #[test]
+    fn test_compound_world_shape_and_aabb() {
+        let collider = Collider::Compound(vec![
+            Collider::Circle(Circle::new(Vector2D::new(-1.0, 0.0), 0.5)),
+            Collider::Box(BoundingBox::new(Vector2D::new(1.0, 0.0), 0.5, 1.0)),
+        ]);
+
+        let aabb = collider.aabb(Vector2D::new(0.0, 1.0), 0.0);
+        assert_eq!(aabb.min().x, -1.5);
+        assert_eq!(aabb.min().y, 0.0);
+        assert_eq!(aabb.max().x, 1.5);
+        assert_eq!(aabb.max().y, 2.0);
+
+        match collider.world_shape(Vector2D::new(0.0, 0.0), std::f32::consts::PI) {
+            Collider::Compound(children) => match children[0] {
+                Collider::Circle(circle) => assert!((circle.center.x - 1.0).abs() < 1e-6),
+                _ => panic!("Expected a circle"),
+            },
+            _ => panic!("Expected a compound"),
+        }
+    }
+
+    #[test]
+    fn test_polygon_rotates_with_body() {
+        let collider = Collider::Polygon(Polygon::rectangle(2.0, 0.5));
+
+        let aabb = collider.aabb(Vector2D::new(1.0, 0.0), std::f32::consts::FRAC_PI_2);
+        assert!((aabb.half_width - 0.5).abs() < 1e-6);
+        assert!((aabb.half_height - 2.0).abs() < 1e-6);
+        assert!((aabb.center.x - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_box_rotates_with_body() {
+        let collider = Collider::rectangle(1.0, 1.0);
+        assert!(matches!(collider.world_shape(Vector2D::new(0.0, 0.0), 0.0), Collider::Box(_)));
+
+        let rotated = collider.world_shape(Vector2D::new(3.0, 0.0), std::f32::consts::FRAC_PI_4);
+        assert!(matches!(rotated, Collider::Polygon(_)));
+        let aabb = rotated.world_aabb();
+        assert!((aabb.half_width - std::f32::consts::SQRT_2).abs() < 1e-5);
+        assert!((aabb.center.x - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_capsule_rotates_with_body() {
+        let collider = Collider::capsule(1.0, 0.5);
+
+        let aabb = collider.aabb(Vector2D::new(0.0, 2.0), std::f32::consts::FRAC_PI_2);
+        assert!((aabb.half_width - 1.5).abs() < 1e-6);
+        assert!((aabb.half_height - 0.5).abs() < 1e-6);
+        assert!((aabb.center.y - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_segment_has_no_mass() {
+        let collider = Collider::segment(Vector2D::new(0.0, 0.0), Vector2D::new(4.0, 0.0));
+        let properties = collider.mass_properties(1.0);
+
+        assert_eq!(properties.mass, 0.0);
+        assert_eq!(properties.center_of_mass.x, 2.0);
+    }
+
+    #[test]
+    fn test_mass_properties_polygon_matches_box() {
+        let polygon = Collider::polygon(&[
+            Vector2D::new(-1.0, -0.5),
+            Vector2D::new(1.0, -0.5),
+            Vector2D::new(1.0, 0.5),
+            Vector2D::new(-1.0, 0.5),
+        ]).unwrap().mass_properties(2.0);
+        let rectangle = Collider::rectangle(1.0, 0.5).mass_properties(2.0);
+
+        assert!((polygon.mass - rectangle.mass).abs() < 1e-5);
+        assert!((polygon.inertia - rectangle.inertia).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_mass_properties_compound() {
+        let collider = Collider::Compound(vec![
+            Collider::Box(BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0)),
+            Collider::Box(BoundingBox::new(Vector2D::new(2.0, 0.0), 1.0, 1.0)),
+        ]);
+
+        let properties = collider.mass_properties(1.0);
+        let single = Collider::rectangle(2.0, 1.0).mass_properties(1.0);
+        assert!((properties.mass - single.mass).abs() < 1e-4);
+        assert!((properties.center_of_mass.x - 1.0).abs() < 1e-6);
+        assert!((properties.inertia - single.inertia).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,71 @@
+// Decides which pairs of bodies are allowed to touch. Two bodies in the same non-zero group
+// always collide when the group is positive and never when it is negative, whatever their bits
+// say. Otherwise each body's category has to be in the other's mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub category_bits: u16,
+    pub mask_bits: u16,
+    pub group_index: i16,
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        CollisionFilter::new(0x0001, 0xFFFF)
+    }
+}
+
+impl CollisionFilter {
+    pub fn new(category_bits: u16, mask_bits: u16) -> Self {
+        CollisionFilter { category_bits, mask_bits, group_index: 0 }
+    }
+
+    pub fn with_group_index(mut self, group_index: i16) -> Self {
+        self.group_index = group_index;
+        self
+    }
+
+    pub fn should_collide(&self, other: &CollisionFilter) -> bool {
+        if self.group_index != 0 && self.group_index == other.group_index {
+            return self.group_index > 0;
+        }
+        self.mask_bits & other.category_bits != 0 && other.mask_bits & self.category_bits != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: u16 = 0x0002;
+    const PLAYER_BULLET: u16 = 0x0004;
+    const ENEMY: u16 = 0x0008;
+
+    #[test]
+    fn test_default_collides_with_everything() {
+        assert!(CollisionFilter::default().should_collide(&CollisionFilter::default()));
+    }
+
+    #[test]
+    fn test_masks_must_accept_each_other() {
+        let player = CollisionFilter::new(PLAYER, !PLAYER_BULLET);
+        let bullet = CollisionFilter::new(PLAYER_BULLET, ENEMY | 0x0001);
+        let enemy = CollisionFilter::new(ENEMY, 0xFFFF);
+
+        assert!(!player.should_collide(&bullet));
+        assert!(!bullet.should_collide(&player));
+        assert!(bullet.should_collide(&enemy));
+        assert!(bullet.should_collide(&CollisionFilter::default()));
+        assert!(!bullet.should_collide(&bullet));
+    }
+
+    #[test]
+    fn test_group_index_overrides_masks() {
+        let limb = CollisionFilter::new(PLAYER, 0xFFFF).with_group_index(-1);
+        assert!(!limb.should_collide(&limb));
+        assert!(limb.should_collide(&CollisionFilter::default()));
+
+        let loner = CollisionFilter::new(ENEMY, 0x0000).with_group_index(3);
+        assert!(loner.should_collide(&loner));
+        assert!(!loner.should_collide(&loner.with_group_index(4)));
+    }
+}
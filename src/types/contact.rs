@@ -0,0 +1,69 @@
+use crate::types::vector_2d::Vector2D;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ContactPoint {
+    pub position: Vector2D,
+    pub depth: f32,
+    // Identifies the geometric feature that produced the point, so the same point can be matched
+    // across steps for warm starting.
+    pub id: u32,
+}
+
+impl ContactPoint {
+    pub fn new(position: Vector2D, depth: f32) -> Self {
+        ContactPoint { position, depth, id: 0 }
+    }
+
+    pub fn with_id(mut self, id: u32) -> Self {
+        self.id = id;
+        self
+    }
+}
+
+// The normal always points from the first shape towards the second one.
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    pub normal: Vector2D,
+    pub depth: f32,
+    pub points: [ContactPoint; 2],
+    pub point_count: usize,
+}
+
+impl Contact {
+    pub fn new(normal: Vector2D, depth: f32, point: Vector2D) -> Self {
+        let point = ContactPoint::new(point, depth);
+        Contact {
+            normal,
+            depth,
+            points: [point, point],
+            point_count: 1,
+        }
+    }
+
+    pub fn with_two_points(normal: Vector2D, point_1: ContactPoint, point_2: ContactPoint) -> Self {
+        Contact {
+            normal,
+            depth: f32::max(point_1.depth, point_2.depth),
+            points: [point_1, point_2],
+            point_count: 2,
+        }
+    }
+
+    pub fn points(&self) -> &[ContactPoint] {
+        &self.points[..self.point_count]
+    }
+
+    pub fn with_id_offset(mut self, offset: u32) -> Contact {
+        for point in &mut self.points {
+            point.id += offset;
+        }
+        self
+    }
+
+    pub fn flipped(&self) -> Contact {
+        Contact {
+            normal: self.normal * -1.0,
+            ..*self
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use crate::types::vector_2d::Vector2D;
+use crate::types::world::BodyHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactEventKind {
+    // First step the two bodies touch.
+    Begin,
+    // Every following step they are still touching.
+    Persist,
+    // First step they no longer touch, or one of them was removed.
+    End,
+}
+
+// One event per touching pair of bodies and step. `body_1` always has the lower handle and the
+// normal points from it towards `body_2`. Impulses are the totals the solver applied over every
+// contact point of the pair that step, so they are zero for `End`, which otherwise repeats the
+// last points and normal seen.
+#[derive(Debug, Clone)]
+pub struct ContactEvent {
+    pub kind: ContactEventKind,
+    pub body_1: BodyHandle,
+    pub body_2: BodyHandle,
+    pub points: Vec<Vector2D>,
+    pub normal: Vector2D,
+    pub normal_impulse: f32,
+    // Along the normal rotated a quarter turn clockwise.
+    pub tangent_impulse: f32,
+}
@@ -0,0 +1,78 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::capsule::Capsule;
+use crate::types::circle::Circle;
+use crate::types::polygon::Polygon;
+use crate::types::segment::Segment;
+use crate::types::vector_2d::Vector2D;
+
+// Anything convex that can report its furthest point in a direction can be collided through
+// GJK/EPA, with no pair-specific code. Shapes must already be in world space.
+pub trait ConvexShape {
+    // Point of the shape furthest along `direction`. `direction` need not be normalized.
+    fn support(&self, direction: Vector2D) -> Vector2D;
+
+    // Any point inside the shape. Used as a starting guess and as a fallback direction.
+    fn center(&self) -> Vector2D;
+}
+
+impl ConvexShape for Circle {
+    fn support(&self, direction: Vector2D) -> Vector2D {
+        let length = direction.length();
+        if length == 0.0 {
+            return self.center + Vector2D::new(self.radius, 0.0);
+        }
+        self.center + direction * (self.radius / length)
+    }
+
+    fn center(&self) -> Vector2D {
+        self.center
+    }
+}
+
+impl ConvexShape for BoundingBox {
+    fn support(&self, direction: Vector2D) -> Vector2D {
+        Vector2D::new(
+            self.center.x + self.half_width.copysign(direction.x),
+            self.center.y + self.half_height.copysign(direction.y),
+        )
+    }
+
+    fn center(&self) -> Vector2D {
+        self.center
+    }
+}
+
+impl ConvexShape for Polygon {
+    fn support(&self, direction: Vector2D) -> Vector2D {
+        self.vertices()[Polygon::support(self, direction)]
+    }
+
+    fn center(&self) -> Vector2D {
+        self.centroid()
+    }
+}
+
+impl ConvexShape for Segment {
+    fn support(&self, direction: Vector2D) -> Vector2D {
+        if self.end.dot(&direction) > self.start.dot(&direction) { self.end } else { self.start }
+    }
+
+    fn center(&self) -> Vector2D {
+        self.midpoint()
+    }
+}
+
+impl ConvexShape for Capsule {
+    fn support(&self, direction: Vector2D) -> Vector2D {
+        let length = direction.length();
+        let core = self.segment().support(direction);
+        if length == 0.0 {
+            return core;
+        }
+        core + direction * (self.radius / length)
+    }
+
+    fn center(&self) -> Vector2D {
+        Capsule::center(self)
+    }
+}
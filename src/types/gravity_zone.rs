@@ -0,0 +1,19 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::vector_2d::Vector2D;
+
+// Rectangular volume whose gravity replaces the world gravity for bodies inside it.
+#[derive(Debug, Clone, Copy)]
+pub struct GravityZone {
+    pub region: BoundingBox,
+    pub gravity: Vector2D,
+}
+
+impl GravityZone {
+    pub fn new(region: BoundingBox, gravity: Vector2D) -> Self {
+        GravityZone { region, gravity }
+    }
+
+    pub fn contains(&self, point: Vector2D) -> bool {
+        self.region.contains_point(point)
+    }
+}
@@ -0,0 +1,243 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::capsule::Capsule;
+use crate::types::circle::Circle;
+use crate::types::vector_2d::Vector2D;
+
+// `inertia` is the rotational inertia about `center_of_mass`, not about the local origin.
+#[derive(Debug, Clone, Copy)]
+pub struct MassProperties {
+    pub mass: f32,
+    pub center_of_mass: Vector2D,
+    pub inertia: f32,
+}
+
+impl MassProperties {
+    pub fn new(mass: f32, center_of_mass: Vector2D, inertia: f32) -> Self {
+        MassProperties { mass, center_of_mass, inertia }
+    }
+
+    pub fn circle(circle: &Circle, density: f32) -> Self {
+        let mass = density * std::f32::consts::PI * circle.radius * circle.radius;
+        MassProperties::new(mass, circle.center, 0.5 * mass * circle.radius * circle.radius)
+    }
+
+    pub fn rectangle(bounding_box: &BoundingBox, density: f32) -> Self {
+        let mass = density * 4.0 * bounding_box.half_width * bounding_box.half_height;
+        let inertia = mass * (bounding_box.half_width * bounding_box.half_width +
+            bounding_box.half_height * bounding_box.half_height) / 3.0;
+        MassProperties::new(mass, bounding_box.center, inertia)
+    }
+
+    // A rectangle along the segment plus a half disc on each end.
+    pub fn capsule(capsule: &Capsule, density: f32) -> Self {
+        let radius = capsule.radius;
+        let length = capsule.segment().length();
+        let rectangle_mass = density * 2.0 * radius * length;
+        let disc_mass = density * std::f32::consts::PI * radius * radius;
+
+        let rectangle_inertia = rectangle_mass * (length * length + 4.0 * radius * radius) / 12.0;
+        // Each half disc has its centroid 4r / 3pi beyond the end of the segment. Shifting the
+        // disc inertia from that centroid to the capsule center adds up to this.
+        let half_length = 0.5 * length;
+        let centroid_offset = 4.0 * radius / (3.0 * std::f32::consts::PI);
+        let disc_inertia = disc_mass * (0.5 * radius * radius + half_length * half_length + 2.0 * half_length * centroid_offset);
+
+        MassProperties::new(rectangle_mass + disc_mass, capsule.center(), rectangle_inertia + disc_inertia)
+    }
+
+    // Vertices must describe a simple polygon in counter-clockwise order.
+    pub fn polygon(vertices: &[Vector2D], density: f32) -> Self {
+        if vertices.len() < 3 {
+            return MassProperties::new(0.0, centroid_of_points(vertices), 0.0);
+        }
+
+        // Triangle fan around the first vertex keeps the numbers small for far-off polygons.
+        let origin = vertices[0];
+        let mut area = 0.0;
+        let mut weighted_center = Vector2D::new(0.0, 0.0);
+        let mut origin_inertia = 0.0;
+
+        for i in 1..vertices.len() - 1 {
+            let e_1 = vertices[i] - origin;
+            let e_2 = vertices[i + 1] - origin;
+            let cross = e_1.cross(&e_2);
+            let triangle_area = 0.5 * cross;
+            area += triangle_area;
+            weighted_center = weighted_center + (e_1 + e_2) * (triangle_area / 3.0);
+
+            let integral_x = e_1.x * e_1.x + e_2.x * e_1.x + e_2.x * e_2.x;
+            let integral_y = e_1.y * e_1.y + e_2.y * e_1.y + e_2.y * e_2.y;
+            origin_inertia += (cross / 12.0) * (integral_x + integral_y);
+        }
+
+        if area <= 0.0 {
+            return MassProperties::new(0.0, centroid_of_points(vertices), 0.0);
+        }
+
+        let local_center = weighted_center / area;
+        let mass = density * area;
+        let inertia = density * origin_inertia - mass * local_center.dot(&local_center);
+        MassProperties::new(mass, origin + local_center, inertia)
+    }
+
+    // Merges parts expressed in the same frame using the parallel axis theorem.
+    pub fn combine(parts: &[MassProperties]) -> Self {
+        let mass: f32 = parts.iter().map(|part| part.mass).sum();
+        if mass == 0.0 {
+            return MassProperties::new(0.0, Vector2D::new(0.0, 0.0), 0.0);
+        }
+
+        let center_of_mass = parts.iter()
+            .fold(Vector2D::new(0.0, 0.0), |sum, part| sum + part.center_of_mass * part.mass) / mass;
+        let inertia = parts.iter().map(|part| {
+            let offset = part.center_of_mass - center_of_mass;
+            part.inertia + part.mass * offset.dot(&offset)
+        }).sum();
+        MassProperties::new(mass, center_of_mass, inertia)
+    }
+
+    pub fn inertia_about(&self, point: Vector2D) -> f32 {
+        let offset = self.center_of_mass - point;
+        self.inertia + self.mass * offset.dot(&offset)
+    }
+}
+
+fn centroid_of_points(points: &[Vector2D]) -> Vector2D {
+    if points.is_empty() {
+        return Vector2D::new(0.0, 0.0);
+    }
+    points.iter().fold(Vector2D::new(0.0, 0.0), |sum, point| sum + *point) / points.len() as f32
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-4 * f32::max(1.0, b.abs())
+    }
+
+    #[test]
+    fn test_circle() {
+        let properties = MassProperties::circle(&Circle::new(Vector2D::new(1.0, 2.0), 2.0), 3.0);
+        let mass = 3.0 * PI * 4.0;
+        assert!(approx_eq(properties.mass, mass));
+        assert_eq!(properties.center_of_mass.x, 1.0);
+        assert_eq!(properties.center_of_mass.y, 2.0);
+        // Solid disc: I = m r^2 / 2
+        assert!(approx_eq(properties.inertia, 0.5 * mass * 4.0));
+    }
+
+    #[test]
+    fn test_rectangle() {
+        let properties = MassProperties::rectangle(&BoundingBox::new(Vector2D::new(0.0, 0.0), 2.0, 0.5), 2.0);
+        // 4 x 1 plate: m = 8, I = m (w^2 + h^2) / 12
+        assert!(approx_eq(properties.mass, 8.0));
+        assert!(approx_eq(properties.inertia, 8.0 * (16.0 + 1.0) / 12.0));
+    }
+
+    #[test]
+    fn test_capsule_without_length_is_a_circle() {
+        let capsule = Capsule::new(Vector2D::new(1.0, 1.0), Vector2D::new(1.0, 1.0), 0.5);
+        let circle = Circle::new(Vector2D::new(1.0, 1.0), 0.5);
+
+        let from_capsule = MassProperties::capsule(&capsule, 2.0);
+        let from_circle = MassProperties::circle(&circle, 2.0);
+        assert!(approx_eq(from_capsule.mass, from_circle.mass));
+        assert!(approx_eq(from_capsule.inertia, from_circle.inertia));
+    }
+
+    #[test]
+    fn test_capsule_matches_fine_polygon() {
+        let capsule = Capsule::new(Vector2D::new(-1.0, 2.0), Vector2D::new(1.0, 2.0), 0.5);
+        // Capsule outline traced with many points, counter-clockwise.
+        let arc_points = 200;
+        let vertices: Vec<Vector2D> = (0..=arc_points)
+            .map(|i| (capsule.end, -PI / 2.0 + PI * i as f32 / arc_points as f32))
+            .chain((0..=arc_points).map(|i| (capsule.start, PI / 2.0 + PI * i as f32 / arc_points as f32)))
+            .map(|(center, angle)| center + Vector2D::new(angle.cos(), angle.sin()) * capsule.radius)
+            .collect();
+
+        let expected = MassProperties::polygon(&vertices, 3.0);
+        let properties = MassProperties::capsule(&capsule, 3.0);
+        assert!((properties.mass - expected.mass).abs() < 1e-3 * expected.mass);
+        assert!(approx_eq(properties.center_of_mass.x, 0.0));
+        assert!(approx_eq(properties.center_of_mass.y, 2.0));
+        assert!((properties.inertia - expected.inertia).abs() < 1e-3 * expected.inertia,
+                "Inertia {} vs {}", properties.inertia, expected.inertia);
+    }
+
+    #[test]
+    fn test_polygon_matches_rectangle() {
+        let vertices = [
+            Vector2D::new(1.0, 1.0),
+            Vector2D::new(5.0, 1.0),
+            Vector2D::new(5.0, 2.0),
+            Vector2D::new(1.0, 2.0),
+        ];
+        let polygon = MassProperties::polygon(&vertices, 2.0);
+        let rectangle = MassProperties::rectangle(&BoundingBox::new(Vector2D::new(3.0, 1.5), 2.0, 0.5), 2.0);
+
+        assert!(approx_eq(polygon.mass, rectangle.mass));
+        assert!(approx_eq(polygon.center_of_mass.x, 3.0));
+        assert!(approx_eq(polygon.center_of_mass.y, 1.5));
+        assert!(approx_eq(polygon.inertia, rectangle.inertia));
+    }
+
+    #[test]
+    fn test_polygon_right_triangle() {
+        let vertices = [
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(3.0, 0.0),
+            Vector2D::new(0.0, 3.0),
+        ];
+        let properties = MassProperties::polygon(&vertices, 1.0);
+        let mass = 4.5;
+        assert!(approx_eq(properties.mass, mass));
+        assert!(approx_eq(properties.center_of_mass.x, 1.0));
+        assert!(approx_eq(properties.center_of_mass.y, 1.0));
+        // Right isosceles triangle with legs a about its centroid: I = m a^2 / 9
+        assert!(approx_eq(properties.inertia, mass * 9.0 / 9.0));
+    }
+
+    #[test]
+    fn test_polygon_degenerate() {
+        let vertices = [Vector2D::new(0.0, 0.0), Vector2D::new(2.0, 0.0)];
+        let properties = MassProperties::polygon(&vertices, 1.0);
+        assert_eq!(properties.mass, 0.0);
+        assert_eq!(properties.center_of_mass.x, 1.0);
+    }
+
+    #[test]
+    fn test_combine_dumbbell() {
+        let left = MassProperties::circle(&Circle::new(Vector2D::new(-2.0, 0.0), 0.5), 1.0);
+        let right = MassProperties::circle(&Circle::new(Vector2D::new(2.0, 0.0), 0.5), 1.0);
+
+        let combined = MassProperties::combine(&[left, right]);
+
+        assert!(approx_eq(combined.mass, 2.0 * left.mass));
+        assert!(approx_eq(combined.center_of_mass.x, 0.0));
+        assert!(approx_eq(combined.inertia, 2.0 * (left.inertia + left.mass * 4.0)));
+    }
+
+    #[test]
+    fn test_combine_shifts_center_of_mass() {
+        let heavy = MassProperties::new(3.0, Vector2D::new(0.0, 0.0), 1.0);
+        let light = MassProperties::new(1.0, Vector2D::new(4.0, 0.0), 1.0);
+
+        let combined = MassProperties::combine(&[heavy, light]);
+
+        assert!(approx_eq(combined.center_of_mass.x, 1.0));
+        assert!(approx_eq(combined.inertia, 2.0 + 3.0 * 1.0 + 1.0 * 9.0));
+        assert!(approx_eq(combined.inertia_about(Vector2D::new(0.0, 0.0)), 1.0 + 1.0 + 16.0));
+    }
+
+    #[test]
+    fn test_combine_empty() {
+        let combined = MassProperties::combine(&[]);
+        assert_eq!(combined.mass, 0.0);
+        assert_eq!(combined.inertia, 0.0);
+    }
+}
@@ -0,0 +1,111 @@
+// How the values of two touching materials are merged. When the two materials ask for different
+// rules, the one further down this list wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum CombineRule {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    pub fn combine(self, value_1: f32, value_2: f32) -> f32 {
+        match self {
+            CombineRule::Average => 0.5 * (value_1 + value_2),
+            CombineRule::Min => f32::min(value_1, value_2),
+            CombineRule::Multiply => value_1 * value_2,
+            CombineRule::Max => f32::max(value_1, value_2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub density: f32,
+    pub restitution: f32,
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    pub restitution_combine: CombineRule,
+    pub friction_combine: CombineRule,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material::new(1.0, 0.5, 0.5, 0.3)
+    }
+}
+
+impl Material {
+    pub fn new(density: f32, restitution: f32, static_friction: f32, dynamic_friction: f32) -> Self {
+        Material {
+            density,
+            restitution,
+            static_friction,
+            dynamic_friction,
+            restitution_combine: CombineRule::default(),
+            friction_combine: CombineRule::default(),
+        }
+    }
+
+    pub fn with_restitution_combine(mut self, rule: CombineRule) -> Self {
+        self.restitution_combine = rule;
+        self
+    }
+
+    pub fn with_friction_combine(mut self, rule: CombineRule) -> Self {
+        self.friction_combine = rule;
+        self
+    }
+
+    pub fn combined_restitution(&self, other: &Material) -> f32 {
+        let rule = self.restitution_combine.max(other.restitution_combine);
+        rule.combine(self.restitution, other.restitution)
+    }
+
+    // Returns (static, dynamic) friction coefficients for the pair.
+    pub fn combined_friction(&self, other: &Material) -> (f32, f32) {
+        let rule = self.friction_combine.max(other.friction_combine);
+        (
+            rule.combine(self.static_friction, other.static_friction),
+            rule.combine(self.dynamic_friction, other.dynamic_friction),
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combine_rules() {
+        assert_eq!(CombineRule::Average.combine(0.2, 0.6), 0.4);
+        assert_eq!(CombineRule::Min.combine(0.2, 0.6), 0.2);
+        assert_eq!(CombineRule::Multiply.combine(0.5, 0.6), 0.3);
+        assert_eq!(CombineRule::Max.combine(0.2, 0.6), 0.6);
+    }
+
+    #[test]
+    fn test_combined_restitution_uses_highest_priority_rule() {
+        let rubber = Material::new(1.0, 0.9, 1.0, 0.8).with_restitution_combine(CombineRule::Max);
+        let clay = Material::new(1.0, 0.1, 1.0, 0.8).with_restitution_combine(CombineRule::Min);
+
+        assert_eq!(rubber.combined_restitution(&clay), 0.9);
+        assert_eq!(clay.combined_restitution(&rubber), 0.9);
+    }
+
+    #[test]
+    fn test_combined_friction() {
+        let ice = Material::new(1.0, 0.1, 0.1, 0.05).with_friction_combine(CombineRule::Min);
+        let wood = Material::new(1.0, 0.1, 0.6, 0.4);
+
+        let (static_friction, dynamic_friction) = ice.combined_friction(&wood);
+        assert_eq!(static_friction, 0.1);
+        assert_eq!(dynamic_friction, 0.05);
+
+        let (static_friction, dynamic_friction) = wood.combined_friction(&wood);
+        assert_eq!(static_friction, 0.6);
+        assert_eq!(dynamic_friction, 0.4);
+    }
+}
@@ -1,4 +1,21 @@
 pub mod vector_2d;
 pub mod rigid_body;
 pub mod circle;
-pub mod bounding_box;
\ No newline at end of file
+pub mod bounding_box;
+pub mod capsule;
+pub mod chain;
+pub mod collider;
+pub mod collision_filter;
+pub mod contact;
+pub mod contact_event;
+pub mod convex_shape;
+pub mod gravity_zone;
+pub mod mass_properties;
+pub mod material;
+pub mod polygon;
+pub mod pre_solve_contact;
+pub mod segment;
+pub mod sensor_event;
+pub mod solver_settings;
+pub mod stepper;
+pub mod world;
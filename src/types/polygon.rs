@@ -0,0 +1,264 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::vector_2d::Vector2D;
+
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+// Points closer together than this are merged when building the hull.
+const WELD_DISTANCE: f32 = 1e-4;
+
+// Convex polygon with counter-clockwise vertices. `normals[i]` is the outward unit normal of the
+// edge from `vertices[i]` to `vertices[i + 1]`.
+#[derive(Debug, Clone, Copy)]
+pub struct Polygon {
+    vertices: [Vector2D; MAX_POLYGON_VERTICES],
+    normals: [Vector2D; MAX_POLYGON_VERTICES],
+    count: usize,
+}
+
+impl Polygon {
+    // Builds the convex hull of `points`. Returns `None` if the hull has fewer than three
+    // vertices (all points on a line) or more than `MAX_POLYGON_VERTICES`.
+    pub fn new(points: &[Vector2D]) -> Option<Self> {
+        let hull = convex_hull(points);
+        if hull.len() < 3 || hull.len() > MAX_POLYGON_VERTICES {
+            return None;
+        }
+
+        let mut polygon = Polygon {
+            vertices: [Vector2D::new(0.0, 0.0); MAX_POLYGON_VERTICES],
+            normals: [Vector2D::new(0.0, 0.0); MAX_POLYGON_VERTICES],
+            count: hull.len(),
+        };
+        polygon.vertices[..hull.len()].copy_from_slice(&hull);
+        polygon.update_normals();
+        Some(polygon)
+    }
+
+    pub fn rectangle(half_width: f32, half_height: f32) -> Self {
+        Polygon::from(BoundingBox::new(Vector2D::new(0.0, 0.0), half_width, half_height))
+    }
+
+    pub fn vertices(&self) -> &[Vector2D] {
+        &self.vertices[..self.count]
+    }
+
+    pub fn normals(&self) -> &[Vector2D] {
+        &self.normals[..self.count]
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn centroid(&self) -> Vector2D {
+        let origin = self.vertices[0];
+        let mut area = 0.0;
+        let mut weighted_center = Vector2D::new(0.0, 0.0);
+        for i in 1..self.count - 1 {
+            let e_1 = self.vertices[i] - origin;
+            let e_2 = self.vertices[i + 1] - origin;
+            let triangle_area = 0.5 * e_1.cross(&e_2);
+            area += triangle_area;
+            weighted_center = weighted_center + (e_1 + e_2) * (triangle_area / 3.0);
+        }
+        if area == 0.0 {
+            // Only a flat box has no area; its vertices still straddle the middle evenly.
+            let sum = self.vertices().iter().fold(Vector2D::new(0.0, 0.0), |sum, vertex| sum + *vertex);
+            return sum / self.count as f32;
+        }
+        origin + weighted_center / area
+    }
+
+    pub fn translated(&self, offset: Vector2D) -> Polygon {
+        let mut polygon = *self;
+        for vertex in &mut polygon.vertices[..self.count] {
+            *vertex = *vertex + offset;
+        }
+        polygon
+    }
+
+    // Rotates about the local origin, then moves to `position`.
+    pub fn transformed(&self, position: Vector2D, angle: f32) -> Polygon {
+        let mut polygon = *self;
+        for i in 0..self.count {
+            polygon.vertices[i] = position + self.vertices[i].rotated(angle);
+            polygon.normals[i] = self.normals[i].rotated(angle);
+        }
+        polygon
+    }
+
+    pub fn contains_point(&self, point: Vector2D) -> bool {
+        self.vertices().iter()
+            .zip(self.normals())
+            .all(|(vertex, normal)| normal.dot(&(point - *vertex)) <= 0.0)
+    }
+
+    pub fn aabb(&self) -> BoundingBox {
+        let first = self.vertices[0];
+        let (min, max) = self.vertices().iter().fold((first, first), |(min, max), vertex| (
+            Vector2D::new(min.x.min(vertex.x), min.y.min(vertex.y)),
+            Vector2D::new(max.x.max(vertex.x), max.y.max(vertex.y)),
+        ));
+        BoundingBox::from_min_max(min, max)
+    }
+
+    // Index of the vertex furthest along `direction`.
+    pub fn support(&self, direction: Vector2D) -> usize {
+        (1..self.count).fold(0, |best, i| {
+            if self.vertices[i].dot(&direction) > self.vertices[best].dot(&direction) { i } else { best }
+        })
+    }
+
+    // Only for hulls, which never have repeated points.
+    fn update_normals(&mut self) {
+        for i in 0..self.count {
+            let edge = self.vertices[(i + 1) % self.count] - self.vertices[i];
+            self.normals[i] = Vector2D::new(edge.y, -edge.x) / edge.length();
+        }
+    }
+}
+
+impl From<BoundingBox> for Polygon {
+    fn from(bounding_box: BoundingBox) -> Self {
+        let (min, max) = (bounding_box.min(), bounding_box.max());
+        let mut polygon = Polygon {
+            vertices: [Vector2D::new(0.0, 0.0); MAX_POLYGON_VERTICES],
+            normals: [Vector2D::new(0.0, 0.0); MAX_POLYGON_VERTICES],
+            count: 4,
+        };
+        polygon.vertices[..4].copy_from_slice(&[
+            min,
+            Vector2D::new(max.x, min.y),
+            max,
+            Vector2D::new(min.x, max.y),
+        ]);
+        // Set directly rather than from the edges, which are zero length for a flat box.
+        polygon.normals[..4].copy_from_slice(&[
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(0.0, 1.0),
+            Vector2D::new(-1.0, 0.0),
+        ]);
+        polygon
+    }
+}
+
+// Andrew's monotone chain. Returns the hull counter-clockwise, without collinear or repeated
+// points.
+fn convex_hull(points: &[Vector2D]) -> Vec<Vector2D> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup_by(|a, b| (*a - *b).length() < WELD_DISTANCE);
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let turns_left = |a: Vector2D, b: Vector2D, c: Vector2D| (b - a).cross(&(c - a)) > 0.0;
+    let mut hull: Vec<Vector2D> = Vec::with_capacity(sorted.len() * 2);
+    // Lower hull left to right, then upper hull right to left.
+    for pass in [sorted.clone(), sorted.iter().rev().copied().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], point) {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point of each chain starts the other one.
+        hull.pop();
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector2D, expected: Vector2D) {
+        assert!((actual - expected).length() < 1e-5, "Expected {:?}, got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn test_convex_hull_drops_inner_and_collinear_points() {
+        let polygon = Polygon::new(&[
+            Vector2D::new(1.0, 1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.5, 0.5),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(0.5, 0.0),
+            Vector2D::new(0.0, 1.0),
+            Vector2D::new(1.0, 1.0),
+        ]).unwrap();
+
+        assert_eq!(polygon.vertex_count(), 4);
+        let expected = [
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(1.0, 1.0),
+            Vector2D::new(0.0, 1.0),
+        ];
+        for (vertex, expected) in polygon.vertices().iter().zip(expected) {
+            assert_close(*vertex, expected);
+        }
+    }
+
+    #[test]
+    fn test_normals_point_outwards() {
+        let triangle = Polygon::new(&[
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 2.0),
+            Vector2D::new(2.0, 0.0),
+        ]).unwrap();
+
+        assert_close(triangle.normals()[0], Vector2D::new(0.0, -1.0));
+        assert_close(triangle.normals()[1], Vector2D::new(f32::sqrt(0.5), f32::sqrt(0.5)));
+        assert_close(triangle.normals()[2], Vector2D::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_flat_box_has_unit_normals() {
+        for polygon in [Polygon::rectangle(0.0, 1.0), Polygon::rectangle(1.0, 0.0), Polygon::rectangle(0.0, 0.0)] {
+            assert_close(polygon.normals()[0], Vector2D::new(0.0, -1.0));
+            assert_close(polygon.normals()[1], Vector2D::new(1.0, 0.0));
+            assert_close(polygon.normals()[2], Vector2D::new(0.0, 1.0));
+            assert_close(polygon.normals()[3], Vector2D::new(-1.0, 0.0));
+            assert_close(polygon.centroid(), Vector2D::new(0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_degenerate_and_oversized_input() {
+        assert!(Polygon::new(&[Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 1.0), Vector2D::new(2.0, 2.0)]).is_none());
+        assert!(Polygon::new(&[Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0)]).is_none());
+
+        let circle_points: Vec<Vector2D> = (0..MAX_POLYGON_VERTICES + 1)
+            .map(|i| Vector2D::new(1.0, 0.0).rotated(i as f32 * std::f32::consts::TAU / (MAX_POLYGON_VERTICES + 1) as f32))
+            .collect();
+        assert!(Polygon::new(&circle_points).is_none());
+        assert!(Polygon::new(&circle_points[..MAX_POLYGON_VERTICES]).is_some());
+    }
+
+    #[test]
+    fn test_transformed() {
+        let polygon = Polygon::rectangle(2.0, 1.0).transformed(Vector2D::new(1.0, 1.0), std::f32::consts::FRAC_PI_2);
+
+        assert_close(polygon.vertices()[0], Vector2D::new(2.0, -1.0));
+        assert_close(polygon.normals()[0], Vector2D::new(1.0, 0.0));
+        let aabb = polygon.aabb();
+        assert_close(aabb.min(), Vector2D::new(0.0, -1.0));
+        assert_close(aabb.max(), Vector2D::new(2.0, 3.0));
+    }
+
+    #[test]
+    fn test_centroid_and_support() {
+        let polygon = Polygon::new(&[
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(3.0, 0.0),
+            Vector2D::new(0.0, 3.0),
+        ]).unwrap();
+
+        assert_close(polygon.centroid(), Vector2D::new(1.0, 1.0));
+        assert_eq!(polygon.vertices()[polygon.support(Vector2D::new(1.0, -0.1))].x, 3.0);
+        assert_eq!(polygon.vertices()[polygon.support(Vector2D::new(-1.0, 1.0))].y, 3.0);
+    }
+}
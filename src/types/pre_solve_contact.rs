@@ -0,0 +1,41 @@
+use crate::types::contact::Contact;
+use crate::types::material::Material;
+use crate::types::rigid_body::RigidBody;
+use crate::types::world::BodyHandle;
+
+// Response values the solver uses for one contact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactProperties {
+    pub restitution: f32,
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    // Speed the second body's surface is dragged to relative to the first one's, along the normal
+    // turned a quarter turn clockwise. Friction pulls towards it the way a conveyor belt does.
+    pub tangent_speed: f32,
+}
+
+impl ContactProperties {
+    pub fn mixed(material_1: &Material, material_2: &Material) -> Self {
+        let (static_friction, dynamic_friction) = material_1.combined_friction(material_2);
+        ContactProperties {
+            restitution: material_1.combined_restitution(material_2),
+            static_friction,
+            dynamic_friction,
+            tangent_speed: 0.0,
+        }
+    }
+}
+
+// Handed to the pre-solve hook once per contact, after the narrow phase and before the solver
+// runs. Clearing `enabled` drops the contact for this step only.
+#[derive(Debug)]
+pub struct PreSolveContact<'a> {
+    pub handle_1: BodyHandle,
+    pub handle_2: BodyHandle,
+    pub body_1: &'a RigidBody,
+    pub body_2: &'a RigidBody,
+    // The normal points from `body_1` towards `body_2`.
+    pub contact: &'a Contact,
+    pub enabled: bool,
+    pub properties: ContactProperties,
+}
@@ -1,35 +1,173 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::collider::Collider;
+use crate::types::collision_filter::CollisionFilter;
+use crate::types::mass_properties::MassProperties;
+use crate::types::material::Material;
 use crate::types::vector_2d::Vector2D;
+use crate::utilities::math_operations::inverse;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct RigidBody {
     pub mass: f32,
     pub inv_mass: f32,
     pub position: Vector2D,
+    // Position at the start of the last step, used for render interpolation. Move bodies with
+    // `teleport` to keep a jump from being drawn as motion.
+    pub previous_position: Vector2D,
     pub velocity: Vector2D,
     pub force: Vector2D,
+    // Counter-clockwise rotation in radians.
+    pub angle: f32,
+    pub previous_angle: f32,
+    pub angular_velocity: f32,
+    pub torque: f32,
+    // Zero inertia locks rotation, the same way zero mass makes a body static.
+    pub inertia: f32,
+    pub inv_inertia: f32,
+    pub collider: Option<Collider>,
+    pub material: Material,
+    pub filter: CollisionFilter,
+    pub gravity_scale: f32,
+    // Swept against the other bodies every step so it cannot pass through thin geometry. Costs
+    // a time of impact query per nearby body, so keep it for small, fast things.
+    pub bullet: bool,
+    // Reports overlaps through the world's sensor events but never pushes anything away.
+    pub sensor: bool,
 }
 
 impl RigidBody {
     pub fn new(mass: f32, position: Vector2D, velocity: Vector2D, force: Vector2D) -> Self {
-        let inv_mass = if mass == 0.0 {
-            0.0f32
-        }
-        else {
-            1.0 / mass
-        };
-
         RigidBody {
             mass,
-            inv_mass,
+            inv_mass: inverse(mass),
             position,
+            previous_position: position,
             velocity,
             force,
+            angle: 0.0,
+            previous_angle: 0.0,
+            angular_velocity: 0.0,
+            torque: 0.0,
+            inertia: 0.0,
+            inv_inertia: 0.0,
+            collider: None,
+            material: Material::default(),
+            filter: CollisionFilter::default(),
+            gravity_scale: 1.0,
+            bullet: false,
+            sensor: false,
         }
     }
 
+    // Derives mass and inertia from the collider. The collider is shifted so its center of mass
+    // sits on the body origin, and the body is placed so the shape ends up where it was
+    // described relative to `position`.
+    pub fn from_collider(collider: Collider, density: f32, position: Vector2D) -> Self {
+        let properties = collider.mass_properties(density);
+        RigidBody::new(
+            properties.mass,
+            position + properties.center_of_mass,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_inertia(properties.inertia)
+            .with_collider(collider.translated(properties.center_of_mass * -1.0))
+    }
+
+    pub fn from_material(collider: Collider, material: Material, position: Vector2D) -> Self {
+        RigidBody::from_collider(collider, material.density, position).with_material(material)
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: CollisionFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_mass_properties(mut self, properties: MassProperties) -> Self {
+        self.mass = properties.mass;
+        self.inv_mass = inverse(properties.mass);
+        self.with_inertia(properties.inertia)
+    }
+
+    pub fn with_collider(mut self, collider: Collider) -> Self {
+        self.collider = Some(collider);
+        self
+    }
+
+    pub fn with_inertia(mut self, inertia: f32) -> Self {
+        self.inertia = inertia;
+        self.inv_inertia = inverse(inertia);
+        self
+    }
+
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self.previous_angle = angle;
+        self
+    }
+
+    pub fn with_angular_velocity(mut self, angular_velocity: f32) -> Self {
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, gravity_scale: f32) -> Self {
+        self.gravity_scale = gravity_scale;
+        self
+    }
+
+    pub fn with_bullet(mut self, bullet: bool) -> Self {
+        self.bullet = bullet;
+        self
+    }
+
+    pub fn with_sensor(mut self, sensor: bool) -> Self {
+        self.sensor = sensor;
+        self
+    }
+
+    // Moves the body without interpolating from where it was.
+    pub fn teleport(&mut self, position: Vector2D, angle: f32) {
+        self.position = position;
+        self.previous_position = position;
+        self.angle = angle;
+        self.previous_angle = angle;
+    }
+
+    pub fn world_collider(&self) -> Option<Collider> {
+        self.collider.as_ref().map(|collider| collider.world_shape(self.position, self.angle))
+    }
+
+    pub fn aabb(&self) -> Option<BoundingBox> {
+        self.collider.as_ref().map(|collider| collider.aabb(self.position, self.angle))
+    }
+
     pub fn apply_force(&mut self, force: Vector2D) {
         self.force = self.force + force;
     }
+
+    // `point` is in world coordinates.
+    pub fn apply_force_at_point(&mut self, force: Vector2D, point: Vector2D) {
+        self.force = self.force + force;
+        self.torque += (point - self.position).cross(&force);
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    pub fn apply_impulse_at_point(&mut self, impulse: Vector2D, point: Vector2D) {
+        self.velocity = self.velocity + impulse * self.inv_mass;
+        self.angular_velocity += (point - self.position).cross(&impulse) * self.inv_inertia;
+    }
+
+    pub fn velocity_at_point(&self, point: Vector2D) -> Vector2D {
+        self.velocity + (point - self.position).perpendicular() * self.angular_velocity
+    }
 }
 
 
@@ -47,6 +185,120 @@ mod tests {
         assert_eq!(body.velocity.x, 0.0);
         assert_eq!(body.velocity.y, 0.0);
         assert_eq!(body.force.x, 0.0);
+        assert!(body.collider.is_none());
+        assert_eq!(body.gravity_scale, 1.0);
+        assert_eq!(body.angle, 0.0);
+        assert_eq!(body.inv_inertia, 0.0);
+    }
+
+    #[test]
+    fn test_from_collider_circle() {
+        let body = RigidBody::from_collider(Collider::circle(1.0), 2.0, Vector2D::new(3.0, 4.0));
+        assert!((body.mass - 2.0 * std::f32::consts::PI).abs() < 1e-4);
+        assert!((body.inertia - 0.5 * body.mass).abs() < 1e-4);
+        assert_eq!(body.position.x, 3.0);
+        assert_eq!(body.position.y, 4.0);
+    }
+
+    #[test]
+    fn test_from_collider_recenters_compound() {
+        use crate::types::bounding_box::BoundingBox;
+
+        let collider = Collider::Compound(vec![
+            Collider::Box(BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0)),
+            Collider::Box(BoundingBox::new(Vector2D::new(2.0, 0.0), 1.0, 1.0)),
+        ]);
+        let body = RigidBody::from_collider(collider, 1.0, Vector2D::new(10.0, 0.0));
+
+        assert_eq!(body.mass, 8.0);
+        assert_eq!(body.position.x, 11.0);
+        let aabb = body.aabb().unwrap();
+        assert_eq!(aabb.min().x, 9.0);
+        assert_eq!(aabb.max().x, 13.0);
+    }
+
+    #[test]
+    fn test_from_material_uses_density() {
+        let material = Material::new(3.0, 0.2, 0.8, 0.6);
+        let body = RigidBody::from_material(Collider::rectangle(1.0, 1.0), material, Vector2D::new(0.0, 0.0));
+        assert_eq!(body.mass, 12.0);
+        assert_eq!(body.material.restitution, 0.2);
+    }
+
+    #[test]
+    fn test_with_mass_properties() {
+        let body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_mass_properties(MassProperties::new(4.0, Vector2D::new(0.0, 0.0), 2.0));
+        assert_eq!(body.inv_mass, 0.25);
+        assert_eq!(body.inv_inertia, 0.5);
+    }
+
+    #[test]
+    fn test_with_inertia() {
+        let body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_inertia(4.0);
+        assert_eq!(body.inertia, 4.0);
+        assert_eq!(body.inv_inertia, 0.25);
+    }
+
+    #[test]
+    fn test_apply_force_at_point() {
+        let mut body = RigidBody::new(1.0, Vector2D::new(1.0, 1.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0));
+        body.apply_force_at_point(Vector2D::new(0.0, 2.0), Vector2D::new(3.0, 1.0));
+        assert_eq!(body.force.y, 2.0);
+        assert_eq!(body.torque, 4.0);
+
+        body.apply_force_at_point(Vector2D::new(0.0, 2.0), Vector2D::new(1.0, 5.0));
+        assert_eq!(body.force.y, 4.0);
+        assert_eq!(body.torque, 4.0);
+    }
+
+    #[test]
+    fn test_apply_impulse_at_point() {
+        let mut body = RigidBody::new(2.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_inertia(0.5);
+        body.apply_impulse_at_point(Vector2D::new(0.0, 1.0), Vector2D::new(1.0, 0.0));
+        assert_eq!(body.velocity.y, 0.5);
+        assert_eq!(body.angular_velocity, 2.0);
+    }
+
+    #[test]
+    fn test_velocity_at_point() {
+        let body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_angular_velocity(2.0);
+        let velocity = body.velocity_at_point(Vector2D::new(0.0, 1.0));
+        assert_eq!(velocity.x, -1.0);
+        assert_eq!(velocity.y, 0.0);
+    }
+
+    #[test]
+    fn test_rigid_body_with_collider() {
+        let body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::circle(2.0));
+        match &body.collider {
+            Some(Collider::Circle(circle)) => assert_eq!(circle.radius, 2.0),
+            _ => panic!("Expected a circle collider"),
+        }
+    }
+
+    #[test]
+    fn test_rigid_body_aabb_follows_position() {
+        let mut body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(1.0, 2.0));
+        body.position = Vector2D::new(3.0, 4.0);
+
+        let aabb = body.aabb().unwrap();
+        assert_eq!(aabb.min().x, 2.0);
+        assert_eq!(aabb.min().y, 2.0);
+        assert_eq!(aabb.max().x, 4.0);
+        assert_eq!(aabb.max().y, 6.0);
+    }
+
+    #[test]
+    fn test_rigid_body_without_collider_has_no_aabb() {
+        let body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0));
+        assert!(body.aabb().is_none());
+        assert!(body.world_collider().is_none());
     }
 
     #[test]
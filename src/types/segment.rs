@@ -0,0 +1,150 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::vector_2d::Vector2D;
+
+// Straight line between two points, with no thickness.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    pub start: Vector2D,
+    pub end: Vector2D,
+}
+
+impl Segment {
+    pub fn new(start: Vector2D, end: Vector2D) -> Self {
+        Segment { start, end }
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.end - self.start).length()
+    }
+
+    pub fn midpoint(&self) -> Vector2D {
+        (self.start + self.end) * 0.5
+    }
+
+    pub fn translated(&self, offset: Vector2D) -> Segment {
+        Segment::new(self.start + offset, self.end + offset)
+    }
+
+    // Rotates about the local origin, then moves to `position`.
+    pub fn transformed(&self, position: Vector2D, angle: f32) -> Segment {
+        Segment::new(position + self.start.rotated(angle), position + self.end.rotated(angle))
+    }
+
+    pub fn aabb(&self) -> BoundingBox {
+        BoundingBox::from_min_max(
+            Vector2D::new(self.start.x.min(self.end.x), self.start.y.min(self.end.y)),
+            Vector2D::new(self.start.x.max(self.end.x), self.start.y.max(self.end.y)),
+        )
+    }
+
+    // Position along the segment, 0 at `start` and 1 at `end`, of the point closest to `point`.
+    pub fn closest_fraction(&self, point: Vector2D) -> f32 {
+        let direction = self.end - self.start;
+        let squared_length = direction.dot(&direction);
+        if squared_length == 0.0 {
+            return 0.0;
+        }
+        ((point - self.start).dot(&direction) / squared_length).clamp(0.0, 1.0)
+    }
+
+    pub fn point_at(&self, fraction: f32) -> Vector2D {
+        self.start + (self.end - self.start) * fraction
+    }
+
+    pub fn closest_point(&self, point: Vector2D) -> Vector2D {
+        self.point_at(self.closest_fraction(point))
+    }
+
+    // Closest pair of points between the two segments, the first on `self`.
+    pub fn closest_points(&self, other: &Segment) -> (Vector2D, Vector2D) {
+        let direction_1 = self.end - self.start;
+        let direction_2 = other.end - other.start;
+        let offset = self.start - other.start;
+        let length_1 = direction_1.dot(&direction_1);
+        let length_2 = direction_2.dot(&direction_2);
+        let projection_2 = direction_2.dot(&offset);
+
+        if length_1 == 0.0 {
+            return (self.start, other.closest_point(self.start));
+        }
+        if length_2 == 0.0 {
+            return (self.closest_point(other.start), other.start);
+        }
+
+        let projection_1 = direction_1.dot(&offset);
+        let cross_term = direction_1.dot(&direction_2);
+        let denominator = length_1 * length_2 - cross_term * cross_term;
+
+        // Parallel segments have a whole range of closest pairs; any start on `self` will do.
+        let mut fraction_1 = if denominator > 0.0 {
+            ((cross_term * projection_2 - projection_1 * length_2) / denominator).clamp(0.0, 1.0)
+        }
+        else {
+            0.0
+        };
+        let mut fraction_2 = (cross_term * fraction_1 + projection_2) / length_2;
+
+        // Clamping the second fraction moves the closest point on the first segment as well.
+        if fraction_2 < 0.0 {
+            fraction_2 = 0.0;
+            fraction_1 = (-projection_1 / length_1).clamp(0.0, 1.0);
+        }
+        else if fraction_2 > 1.0 {
+            fraction_2 = 1.0;
+            fraction_1 = ((cross_term - projection_1) / length_1).clamp(0.0, 1.0);
+        }
+        (self.point_at(fraction_1), other.point_at(fraction_2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector2D, expected: Vector2D) {
+        assert!((actual - expected).length() < 1e-5, "Expected {:?}, got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn test_closest_point() {
+        let segment = Segment::new(Vector2D::new(0.0, 0.0), Vector2D::new(4.0, 0.0));
+
+        assert_close(segment.closest_point(Vector2D::new(1.0, 3.0)), Vector2D::new(1.0, 0.0));
+        assert_close(segment.closest_point(Vector2D::new(-2.0, 1.0)), Vector2D::new(0.0, 0.0));
+        assert_close(segment.closest_point(Vector2D::new(7.0, -1.0)), Vector2D::new(4.0, 0.0));
+    }
+
+    #[test]
+    fn test_closest_points_crossing_and_skew() {
+        let segment_1 = Segment::new(Vector2D::new(0.0, 0.0), Vector2D::new(4.0, 0.0));
+
+        let crossing = Segment::new(Vector2D::new(2.0, -1.0), Vector2D::new(2.0, 1.0));
+        let (point_1, point_2) = segment_1.closest_points(&crossing);
+        assert_close(point_1, Vector2D::new(2.0, 0.0));
+        assert_close(point_2, Vector2D::new(2.0, 0.0));
+
+        let above_end = Segment::new(Vector2D::new(5.0, 1.0), Vector2D::new(6.0, 3.0));
+        let (point_1, point_2) = segment_1.closest_points(&above_end);
+        assert_close(point_1, Vector2D::new(4.0, 0.0));
+        assert_close(point_2, Vector2D::new(5.0, 1.0));
+
+        let slanted = Segment::new(Vector2D::new(1.0, 2.0), Vector2D::new(3.0, 1.0));
+        let (point_1, point_2) = segment_1.closest_points(&slanted);
+        assert_close(point_1, Vector2D::new(3.0, 0.0));
+        assert_close(point_2, Vector2D::new(3.0, 1.0));
+    }
+
+    #[test]
+    fn test_closest_points_parallel_and_degenerate() {
+        let segment_1 = Segment::new(Vector2D::new(0.0, 0.0), Vector2D::new(4.0, 0.0));
+
+        let parallel = Segment::new(Vector2D::new(1.0, 1.0), Vector2D::new(6.0, 1.0));
+        let (point_1, point_2) = segment_1.closest_points(&parallel);
+        assert!(f32::abs((point_2 - point_1).length() - 1.0) < 1e-5);
+
+        let point = Segment::new(Vector2D::new(2.0, -3.0), Vector2D::new(2.0, -3.0));
+        let (point_1, point_2) = segment_1.closest_points(&point);
+        assert_close(point_1, Vector2D::new(2.0, 0.0));
+        assert_close(point_2, Vector2D::new(2.0, -3.0));
+    }
+}
@@ -0,0 +1,20 @@
+use crate::types::world::BodyHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorEventKind {
+    // First step the two bodies overlap.
+    Begin,
+    // Every following step they are still overlapping.
+    Stay,
+    // First step they no longer overlap, or one of them was removed.
+    End,
+}
+
+// Overlap reported for a sensor body. When both bodies are sensors, `sensor` is the one with the
+// lower handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorEvent {
+    pub kind: SensorEventKind,
+    pub sensor: BodyHandle,
+    pub other: BodyHandle,
+}
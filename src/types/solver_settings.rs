@@ -0,0 +1,26 @@
+#[derive(Debug, Clone, Copy)]
+pub struct SolverSettings {
+    // Fraction of the remaining overlap removed each step (Baumgarte factor).
+    pub correction_factor: f32,
+    // Overlap that is tolerated without correction, which keeps resting contacts from jittering.
+    pub slop: f32,
+    // Closing speeds below this bounce with zero restitution so resting bodies settle.
+    pub restitution_threshold: f32,
+    pub velocity_iterations: u32,
+    pub position_iterations: u32,
+    // Seed each contact with the impulse it ended the previous step with.
+    pub warm_starting: bool,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        SolverSettings {
+            correction_factor: 0.8,
+            slop: 0.01,
+            restitution_threshold: 0.5,
+            velocity_iterations: 8,
+            position_iterations: 3,
+            warm_starting: true,
+        }
+    }
+}
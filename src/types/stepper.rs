@@ -0,0 +1,233 @@
+use crate::types::pre_solve_contact::PreSolveContact;
+use crate::types::vector_2d::Vector2D;
+use crate::types::world::{BodyHandle, World};
+
+// Runs the world at a fixed timestep no matter how long each rendered frame took. Leftover time
+// is carried over in the accumulator and exposed as `alpha` for interpolating between the last
+// two physics states.
+#[derive(Debug, Clone, Copy)]
+pub struct Stepper {
+    pub fixed_dt: f32,
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl Stepper {
+    pub fn new(fixed_dt: f32) -> Self {
+        assert!(fixed_dt > 0.0 && fixed_dt.is_finite(), "Fixed timestep must be positive, got {}", fixed_dt);
+        Stepper {
+            fixed_dt,
+            max_steps: 8,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    // Returns the number of fixed steps that were run.
+    pub fn advance(&mut self, world: &mut World, frame_time: f32) -> u32 {
+        self.advance_with_pre_solve(world, frame_time, |_| {})
+    }
+
+    // Like `advance`, with `pre_solve` handed to every fixed step it runs.
+    pub fn advance_with_pre_solve(&mut self, world: &mut World, frame_time: f32,
+                                  mut pre_solve: impl FnMut(&mut PreSolveContact)) -> u32 {
+        self.accumulator += f32::max(frame_time, 0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_dt {
+            if steps == self.max_steps {
+                // Too far behind to catch up; drop the backlog rather than spiral.
+                self.accumulator %= self.fixed_dt;
+                break;
+            }
+            world.step_with_pre_solve(self.fixed_dt, &mut pre_solve);
+            self.accumulator -= self.fixed_dt;
+            steps += 1;
+        }
+        steps
+    }
+
+    pub fn accumulator(&self) -> f32 {
+        self.accumulator
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.fixed_dt
+    }
+
+    pub fn interpolated_position(&self, world: &World, handle: BodyHandle) -> Option<Vector2D> {
+        let body = world.get(handle)?;
+        Some(lerp(body.previous_position, body.position, self.alpha()))
+    }
+
+    pub fn interpolated_angle(&self, world: &World, handle: BodyHandle) -> Option<f32> {
+        let body = world.get(handle)?;
+        Some(lerp_angle(body.previous_angle, body.angle, self.alpha()))
+    }
+
+    // Position and angle of every body, ready to render.
+    pub fn interpolated_poses(&self, world: &World) -> Vec<(BodyHandle, Vector2D, f32)> {
+        let alpha = self.alpha();
+        world.iter()
+            .map(|(handle, body)| (
+                handle,
+                lerp(body.previous_position, body.position, alpha),
+                lerp_angle(body.previous_angle, body.angle, alpha),
+            ))
+            .collect()
+    }
+}
+
+fn lerp(from: Vector2D, to: Vector2D, alpha: f32) -> Vector2D {
+    from + (to - from) * alpha
+}
+
+fn lerp_angle(from: f32, to: f32, alpha: f32) -> f32 {
+    from + (to - from) * alpha
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::collider::Collider;
+    use crate::types::rigid_body::RigidBody;
+
+    fn moving_world() -> (World, BodyHandle) {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let handle = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(10.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ));
+        (world, handle)
+    }
+
+    #[test]
+    fn test_advance_runs_whole_steps() {
+        let (mut world, handle) = moving_world();
+        let mut stepper = Stepper::new(0.1);
+
+        assert_eq!(stepper.advance(&mut world, 0.25), 2);
+        assert!(f32::abs(stepper.accumulator() - 0.05) < 1e-5);
+        assert!(f32::abs(world.get(handle).unwrap().position.x - 2.0) < 1e-5);
+
+        assert_eq!(stepper.advance(&mut world, 0.05), 1);
+        assert!(stepper.accumulator() < 1e-5);
+    }
+
+    #[test]
+    fn test_advance_short_frame_runs_no_step() {
+        let (mut world, handle) = moving_world();
+        let mut stepper = Stepper::new(0.1);
+
+        assert_eq!(stepper.advance(&mut world, 0.04), 0);
+        assert_eq!(world.get(handle).unwrap().position.x, 0.0);
+    }
+
+    #[test]
+    fn test_advance_clamps_spiral_of_death() {
+        let (mut world, handle) = moving_world();
+        let mut stepper = Stepper::new(0.1).with_max_steps(3);
+
+        assert_eq!(stepper.advance(&mut world, 5.05), 3);
+        assert!(stepper.accumulator() < stepper.fixed_dt);
+        assert!(f32::abs(world.get(handle).unwrap().position.x - 3.0) < 1e-4);
+    }
+
+    #[test]
+    fn test_advance_ignores_negative_frame_time() {
+        let (mut world, _) = moving_world();
+        let mut stepper = Stepper::new(0.1);
+
+        assert_eq!(stepper.advance(&mut world, -1.0), 0);
+        assert_eq!(stepper.accumulator(), 0.0);
+    }
+
+    #[test]
+    fn test_advance_with_pre_solve_runs_hook_every_step() {
+        let mut world = World::new();
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)));
+        let ball = world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, 0.5)));
+        let mut stepper = Stepper::new(0.1);
+
+        let mut calls = 0;
+        let steps = stepper.advance_with_pre_solve(&mut world, 0.35, |contact| {
+            contact.enabled = false;
+            calls += 1;
+        });
+
+        // With every contact dropped the ball falls through the floor.
+        assert_eq!(steps, 3);
+        assert!(calls >= steps);
+        assert!(world.get(ball).unwrap().position.y < 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_fixed_dt_is_rejected() {
+        Stepper::new(0.0);
+    }
+
+    #[test]
+    fn test_interpolated_position() {
+        let (mut world, handle) = moving_world();
+        let mut stepper = Stepper::new(0.1);
+
+        stepper.advance(&mut world, 0.175);
+
+        assert!(f32::abs(stepper.alpha() - 0.75) < 1e-4);
+        let position = stepper.interpolated_position(&world, handle).unwrap();
+        assert!(f32::abs(position.x - 0.75) < 1e-4);
+
+        assert_eq!(stepper.interpolated_angle(&world, handle).unwrap(), 0.0);
+
+        let poses = stepper.interpolated_poses(&world);
+        assert_eq!(poses.len(), 1);
+        assert_eq!(poses[0].0, handle);
+        assert!(f32::abs(poses[0].1.x - 0.75) < 1e-4);
+        assert_eq!(poses[0].2, 0.0);
+    }
+
+    #[test]
+    fn test_interpolated_poses_follow_rotation() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let handle = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_inertia(1.0).with_angular_velocity(2.0));
+        let mut stepper = Stepper::new(0.1);
+
+        stepper.advance(&mut world, 0.15);
+
+        // Halfway between the start and the end of the only step.
+        let (pose_handle, _, angle) = stepper.interpolated_poses(&world)[0];
+        assert_eq!(pose_handle, handle);
+        assert!(f32::abs(angle - 0.1) < 1e-4, "Got {}", angle);
+    }
+
+    #[test]
+    fn test_teleport_is_not_interpolated() {
+        let (mut world, handle) = moving_world();
+        let mut stepper = Stepper::new(0.1);
+        stepper.advance(&mut world, 0.15);
+
+        world.get_mut(handle).unwrap().teleport(Vector2D::new(50.0, 0.0), 1.0);
+
+        let position = stepper.interpolated_position(&world, handle).unwrap();
+        assert_eq!(position.x, 50.0);
+        assert_eq!(stepper.interpolated_angle(&world, handle).unwrap(), 1.0);
+    }
+}
@@ -20,6 +20,21 @@ impl Vector2D {
         (self.x.powi(2) + self.y.powi(2)).sqrt()
     }
 
+    // Z component of the 3D cross product.
+    pub fn cross(&self, other: &Vector2D) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    // Counter-clockwise perpendicular, i.e. the cross product of a unit z vector with `self`.
+    pub fn perpendicular(&self) -> Vector2D {
+        Vector2D::new(-self.y, self.x)
+    }
+
+    pub fn rotated(&self, angle: f32) -> Vector2D {
+        let (sin, cos) = angle.sin_cos();
+        Vector2D::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
     pub fn normalize(&mut self) {
         let length = self.length();
         if length == 0.0 {
@@ -171,6 +186,32 @@ mod tests {
         assert_eq!(c.y, -0.0000387);
     }
 
+    #[test]
+    fn test_cross() {
+        let a = Vector2D::new(1.0, 0.0);
+        let b = Vector2D::new(0.0, 1.0);
+        assert_eq!(a.cross(&b), 1.0);
+        assert_eq!(b.cross(&a), -1.0);
+        assert_eq!(a.cross(&a), 0.0);
+    }
+
+    #[test]
+    fn test_perpendicular() {
+        let a = Vector2D::new(2.0, 1.0);
+        let b = a.perpendicular();
+        assert_eq!(b.x, -1.0);
+        assert_eq!(b.y, 2.0);
+        assert_eq!(a.dot(&b), 0.0);
+    }
+
+    #[test]
+    fn test_rotated() {
+        let a = Vector2D::new(1.0, 0.0);
+        let b = a.rotated(std::f32::consts::FRAC_PI_2);
+        assert!(b.x.abs() < 1e-6);
+        assert!((b.y - 1.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_mul() {
         let a = Vector2D::new(1.0, 2.0);
@@ -183,22 +224,22 @@ mod tests {
     fn test_div() {
         let a = Vector2D::new(1.0, 2.0);
         let b = a / 3.0;
-        assert_eq!(b.x, 0.3333333333333333);
-        assert_eq!(b.y, 0.6666666666666666);
+        assert_eq!(b.x, 0.333_333_34);
+        assert_eq!(b.y, 0.666_666_7);
     }
 
     #[test]
     fn test_normalize() {
         let mut a = Vector2D::new(1.0, 2.0);
         a.normalize();
-        assert_eq!(a.x, 0.4472135954999579);
-        assert_eq!(a.y, 0.8944271909999158);
+        assert_eq!(a.x, 0.447_213_6);
+        assert_eq!(a.y, 0.894_427_2);
     }
 
     #[test]
     fn test_length() {
         let a = Vector2D::new(1.0, 2.0);
-        assert_eq!(a.length(), 2.23606797749979);
+        assert_eq!(a.length(), 2.236_068);
     }
 
     #[test]
@@ -307,6 +348,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::clone_on_copy)]
     fn test_vector_clone() {
         let a = Vector2D::new(1.0, 2.0);
         let b = a.clone();
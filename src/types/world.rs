@@ -0,0 +1,478 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::contact_event::ContactEvent;
+use crate::types::gravity_zone::GravityZone;
+use crate::types::pre_solve_contact::PreSolveContact;
+use crate::types::rigid_body::RigidBody;
+use crate::types::sensor_event::SensorEvent;
+use crate::types::solver_settings::SolverSettings;
+use crate::types::vector_2d::Vector2D;
+use crate::utilities::broad_phase::BroadPhase;
+use crate::utilities::constants::G;
+use crate::utilities::contact_solver::{CachedImpulse, ContactKey};
+use crate::utilities::math_operations::Integrator;
+use crate::utilities::world_functions::{physics_step, physics_step_with};
+
+/// Stable reference to a body stored in a `World`.
+///
+/// The generation is bumped every time a slot is freed, so a handle to a removed body never
+/// resolves to whatever body later reuses its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle {
+    index: u32,
+    generation: u32,
+}
+
+impl BodyHandle {
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    generation: u32,
+    body: Option<RigidBody>,
+}
+
+#[derive(Debug, Clone)]
+pub struct World {
+    pub gravity: Vector2D,
+    // When zones overlap, the one added last wins.
+    pub gravity_zones: Vec<GravityZone>,
+    pub integrator: Integrator,
+    pub solver_settings: SolverSettings,
+    // Only swapped through `with_broad_phase` or `set_broad_phase`, which leave the new one for
+    // the next step to fill.
+    pub(crate) broad_phase: BroadPhase,
+    // Both event queues are off by default, so worlds that never drain them don't keep piling
+    // events up.
+    pub record_contact_events: bool,
+    pub record_sensor_events: bool,
+    pub(crate) contact_cache: HashMap<ContactKey, CachedImpulse>,
+    // Bodies added or handed out mutably since the last step. The broad phase may not know where
+    // they are, so queries check them directly.
+    pub(crate) unindexed: HashSet<BodyHandle>,
+    // Set whenever any body may have moved without the broad phase knowing: by `iter_mut` and by
+    // swapping the broad phase.
+    pub(crate) all_unindexed: bool,
+    // Stored with the lower handle first.
+    ignored_pairs: HashSet<(BodyHandle, BodyHandle)>,
+    // Pairs that overlapped last step, stored as (sensor, other).
+    pub(crate) sensor_overlaps: HashSet<(BodyHandle, BodyHandle)>,
+    // Grows every step until drained while `record_sensor_events` is set.
+    pub(crate) sensor_events: Vec<SensorEvent>,
+    // Last step's event for every touching pair, keyed (lower handle, higher handle).
+    pub(crate) touching_pairs: HashMap<(BodyHandle, BodyHandle), ContactEvent>,
+    // Grows every step until drained while `record_contact_events` is set.
+    pub(crate) contact_events: Vec<ContactEvent>,
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    body_count: usize,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            gravity: G,
+            gravity_zones: Vec::new(),
+            integrator: Integrator::default(),
+            solver_settings: SolverSettings::default(),
+            broad_phase: BroadPhase::default(),
+            record_contact_events: false,
+            record_sensor_events: false,
+            contact_cache: HashMap::new(),
+            unindexed: HashSet::new(),
+            all_unindexed: false,
+            ignored_pairs: HashSet::new(),
+            sensor_overlaps: HashSet::new(),
+            sensor_events: Vec::new(),
+            touching_pairs: HashMap::new(),
+            contact_events: Vec::new(),
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            body_count: 0,
+        }
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        World::default()
+    }
+
+    pub fn with_gravity(gravity: Vector2D) -> Self {
+        World {
+            gravity,
+            ..World::default()
+        }
+    }
+
+    pub fn with_broad_phase(mut self, broad_phase: BroadPhase) -> Self {
+        self.set_broad_phase(broad_phase);
+        self
+    }
+
+    pub fn broad_phase(&self) -> &BroadPhase {
+        &self.broad_phase
+    }
+
+    pub fn set_broad_phase(&mut self, broad_phase: BroadPhase) {
+        self.broad_phase = broad_phase;
+        self.all_unindexed = true;
+    }
+
+    pub fn with_contact_events(mut self) -> Self {
+        self.record_contact_events = true;
+        self
+    }
+
+    pub fn with_sensor_events(mut self) -> Self {
+        self.record_sensor_events = true;
+        self
+    }
+
+    pub fn add_gravity_zone(&mut self, zone: GravityZone) {
+        self.gravity_zones.push(zone);
+    }
+
+    pub fn gravity_at(&self, point: Vector2D) -> Vector2D {
+        gravity_at(self.gravity, &self.gravity_zones, point)
+    }
+
+    pub fn insert(&mut self, body: RigidBody) -> BodyHandle {
+        self.body_count += 1;
+
+        let handle = if let Some(index) = self.free_slots.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.body = Some(body);
+            BodyHandle { index, generation: slot.generation }
+        }
+        else {
+            let index = self.slots.len() as u32;
+            self.slots.push(Slot { generation: 0, body: Some(body) });
+            BodyHandle { index, generation: 0 }
+        };
+        self.unindexed.insert(handle);
+        handle
+    }
+
+    pub fn remove(&mut self, handle: BodyHandle) -> Option<RigidBody> {
+        let slot = self.slots.get_mut(handle.index())?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+
+        let body = slot.body.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(handle.index);
+        self.body_count -= 1;
+        self.ignored_pairs.retain(|(handle_1, handle_2)| *handle_1 != handle && *handle_2 != handle);
+        Some(body)
+    }
+
+    pub fn contains(&self, handle: BodyHandle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn get(&self, handle: BodyHandle) -> Option<&RigidBody> {
+        let slot = self.slots.get(handle.index())?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.body.as_ref()
+    }
+
+    pub fn get_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody> {
+        let slot = self.slots.get_mut(handle.index())?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        let body = slot.body.as_mut()?;
+        self.unindexed.insert(handle);
+        Some(body)
+    }
+
+    pub fn get_pair_mut(&mut self, handle_1: BodyHandle, handle_2: BodyHandle)
+        -> Option<(&mut RigidBody, &mut RigidBody)> {
+        if handle_1.index == handle_2.index {
+            return None;
+        }
+
+        let (low, high) = if handle_1.index < handle_2.index {
+            (handle_1, handle_2)
+        }
+        else {
+            (handle_2, handle_1)
+        };
+        let (head, tail) = self.slots.split_at_mut(high.index());
+        let low_slot = &mut head[low.index()];
+        let high_slot = &mut tail[0];
+        if low_slot.generation != low.generation || high_slot.generation != high.generation {
+            return None;
+        }
+
+        let low_body = low_slot.body.as_mut()?;
+        let high_body = high_slot.body.as_mut()?;
+        self.unindexed.insert(handle_1);
+        self.unindexed.insert(handle_2);
+        if handle_1.index < handle_2.index {
+            Some((low_body, high_body))
+        }
+        else {
+            Some((high_body, low_body))
+        }
+    }
+
+    // Stops the two bodies colliding, on top of whatever their filters say.
+    pub fn ignore_collision(&mut self, handle_1: BodyHandle, handle_2: BodyHandle) {
+        self.ignored_pairs.insert(ordered(handle_1, handle_2));
+    }
+
+    pub fn restore_collision(&mut self, handle_1: BodyHandle, handle_2: BodyHandle) {
+        self.ignored_pairs.remove(&ordered(handle_1, handle_2));
+    }
+
+    pub fn is_collision_ignored(&self, handle_1: BodyHandle, handle_2: BodyHandle) -> bool {
+        self.ignored_pairs.contains(&ordered(handle_1, handle_2))
+    }
+
+    // False if either body is missing, the pair is ignored, or their filters reject each other.
+    pub fn should_collide(&self, handle_1: BodyHandle, handle_2: BodyHandle) -> bool {
+        let (Some(body_1), Some(body_2)) = (self.get(handle_1), self.get(handle_2)) else {
+            return false;
+        };
+        body_1.filter.should_collide(&body_2.filter) && !self.is_collision_ignored(handle_1, handle_2)
+    }
+
+    // Sensor events of every step since the last drain, oldest first, so nothing is lost when
+    // several steps run between two frames. Stays empty unless `record_sensor_events` is set.
+    pub fn drain_sensor_events(&mut self) -> std::vec::Drain<'_, SensorEvent> {
+        self.sensor_events.drain(..)
+    }
+
+    // Contact events of every step since the last drain, oldest first, so nothing is lost when
+    // several steps run between two frames. Stays empty unless `record_contact_events` is set.
+    pub fn drain_contact_events(&mut self) -> std::vec::Drain<'_, ContactEvent> {
+        self.contact_events.drain(..)
+    }
+
+    pub fn len(&self) -> usize {
+        self.body_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body_count == 0
+    }
+
+    pub fn handles(&self) -> Vec<BodyHandle> {
+        self.iter().map(|(handle, _)| handle).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BodyHandle, &RigidBody)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.body.as_ref().map(|body| {
+                (BodyHandle { index: index as u32, generation: slot.generation }, body)
+            })
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (BodyHandle, &mut RigidBody)> {
+        self.all_unindexed = true;
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            let generation = slot.generation;
+            slot.body.as_mut().map(|body| {
+                (BodyHandle { index: index as u32, generation }, body)
+            })
+        })
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        physics_step(self, dt);
+    }
+
+    // Steps with a hook that can disable or tweak contacts before they are solved, for one-way
+    // platforms, conveyor belts and similar per-pair rules.
+    pub fn step_with_pre_solve(&mut self, dt: f32, pre_solve: impl FnMut(&mut PreSolveContact)) {
+        physics_step_with(self, dt, pre_solve);
+    }
+}
+
+fn ordered(handle_1: BodyHandle, handle_2: BodyHandle) -> (BodyHandle, BodyHandle) {
+    if handle_1 <= handle_2 { (handle_1, handle_2) } else { (handle_2, handle_1) }
+}
+
+pub fn gravity_at(gravity: Vector2D, zones: &[GravityZone], point: Vector2D) -> Vector2D {
+    zones.iter()
+        .rev()
+        .find(|zone| zone.contains(point))
+        .map_or(gravity, |zone| zone.gravity)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::bounding_box::BoundingBox;
+
+    fn body_at(x: f32, y: f32) -> RigidBody {
+        RigidBody::new(
+            1.0,
+            Vector2D::new(x, y),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        )
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut world = World::new();
+        let handle = world.insert(body_at(1.0, 2.0));
+
+        let body = world.get(handle).unwrap();
+        assert_eq!(body.position.x, 1.0);
+        assert_eq!(body.position.y, 2.0);
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut world = World::new();
+        let handle = world.insert(body_at(0.0, 0.0));
+
+        world.get_mut(handle).unwrap().position = Vector2D::new(5.0, 6.0);
+
+        assert_eq!(world.get(handle).unwrap().position.x, 5.0);
+        assert_eq!(world.get(handle).unwrap().position.y, 6.0);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut world = World::new();
+        let handle = world.insert(body_at(3.0, 0.0));
+
+        let removed = world.remove(handle).unwrap();
+        assert_eq!(removed.position.x, 3.0);
+        assert!(world.get(handle).is_none());
+        assert!(world.remove(handle).is_none());
+        assert!(world.is_empty());
+    }
+
+    #[test]
+    fn test_stale_handle_after_slot_reuse() {
+        let mut world = World::new();
+        let old_handle = world.insert(body_at(1.0, 0.0));
+        world.remove(old_handle);
+
+        let new_handle = world.insert(body_at(2.0, 0.0));
+
+        assert_eq!(old_handle.index(), new_handle.index());
+        assert_ne!(old_handle, new_handle);
+        assert!(world.get(old_handle).is_none());
+        assert!(world.get_mut(old_handle).is_none());
+        assert_eq!(world.get(new_handle).unwrap().position.x, 2.0);
+    }
+
+    #[test]
+    fn test_get_pair_mut() {
+        let mut world = World::new();
+        let handle_1 = world.insert(body_at(1.0, 0.0));
+        let handle_2 = world.insert(body_at(2.0, 0.0));
+
+        let (body_2, body_1) = world.get_pair_mut(handle_2, handle_1).unwrap();
+        assert_eq!(body_1.position.x, 1.0);
+        assert_eq!(body_2.position.x, 2.0);
+        body_1.position.x = 10.0;
+
+        assert_eq!(world.get(handle_1).unwrap().position.x, 10.0);
+        assert!(world.get_pair_mut(handle_1, handle_1).is_none());
+    }
+
+    #[test]
+    fn test_iter_skips_removed_bodies() {
+        let mut world = World::new();
+        let handle_1 = world.insert(body_at(1.0, 0.0));
+        let handle_2 = world.insert(body_at(2.0, 0.0));
+        let handle_3 = world.insert(body_at(3.0, 0.0));
+        world.remove(handle_2);
+
+        let handles: Vec<BodyHandle> = world.iter().map(|(handle, _)| handle).collect();
+        assert_eq!(handles, vec![handle_1, handle_3]);
+        assert_eq!(world.handles(), handles);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut world = World::new();
+        world.insert(body_at(1.0, 0.0));
+        world.insert(body_at(2.0, 0.0));
+
+        for (_, body) in world.iter_mut() {
+            body.position.x *= 10.0;
+        }
+
+        let xs: Vec<f32> = world.iter().map(|(_, body)| body.position.x).collect();
+        assert_eq!(xs, vec![10.0, 20.0]);
+    }
+
+    #[test]
+    fn test_insert_during_simulation() {
+        let mut world = World::new();
+        let handle_1 = world.insert(body_at(0.0, 0.0));
+        world.step(1.0);
+
+        let handle_2 = world.insert(body_at(0.0, 0.0));
+        world.step(1.0);
+
+        assert!(world.get(handle_1).unwrap().position.y < world.get(handle_2).unwrap().position.y);
+    }
+
+    #[test]
+    fn test_ignored_pairs() {
+        let mut world = World::new();
+        let handle_1 = world.insert(body_at(0.0, 0.0));
+        let handle_2 = world.insert(body_at(1.0, 0.0));
+        assert!(world.should_collide(handle_1, handle_2));
+
+        world.ignore_collision(handle_2, handle_1);
+        assert!(world.is_collision_ignored(handle_1, handle_2));
+        assert!(!world.should_collide(handle_1, handle_2));
+
+        world.restore_collision(handle_1, handle_2);
+        assert!(world.should_collide(handle_2, handle_1));
+
+        world.ignore_collision(handle_1, handle_2);
+        world.remove(handle_2);
+        let handle_3 = world.insert(body_at(1.0, 0.0));
+        assert!(world.should_collide(handle_1, handle_3));
+        assert!(!world.is_collision_ignored(handle_1, handle_2));
+    }
+
+    #[test]
+    fn test_default_gravity() {
+        let world = World::new();
+        assert_eq!(world.gravity.x, G.x);
+        assert_eq!(world.gravity.y, G.y);
+    }
+
+    #[test]
+    fn test_gravity_at_zones() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 9.81));
+        world.add_gravity_zone(GravityZone::new(
+            BoundingBox::new(Vector2D::new(0.0, 0.0), 2.0, 2.0),
+            Vector2D::new(0.0, 0.0)
+        ));
+        world.add_gravity_zone(GravityZone::new(
+            BoundingBox::new(Vector2D::new(2.0, 0.0), 1.0, 1.0),
+            Vector2D::new(5.0, 0.0)
+        ));
+
+        assert_eq!(world.gravity_at(Vector2D::new(10.0, 0.0)).y, 9.81);
+        assert_eq!(world.gravity_at(Vector2D::new(-1.0, 0.0)).y, 0.0);
+        assert_eq!(world.gravity_at(Vector2D::new(1.5, 0.0)).x, 5.0);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::world::BodyHandle;
+use crate::utilities::collisions::bounding_box_collision;
+use crate::utilities::dynamic_tree::TreeBroadPhase;
+use crate::utilities::spatial_hash::SpatialHash;
+use crate::utilities::sweep_and_prune::{SweepAndPrune, SweepAxis};
+
+// Picks the candidate pairs handed to the narrow phase. Every variant reports exactly the pairs
+// whose AABBs overlap; they only differ in how much work that takes.
+#[derive(Debug, Clone, Default)]
+pub enum BroadPhase {
+    // Tests every pair. Fine for a handful of bodies.
+    #[default]
+    BruteForce,
+    SpatialHash(SpatialHash),
+    // Suited to bodies spread out along one axis.
+    SweepAndPrune(SweepAndPrune),
+    // Copes with bodies of very different sizes and can follow rays through its hierarchy. When
+    // similar bodies all move every step, the spatial hash is usually quicker.
+    DynamicTree(TreeBroadPhase),
+}
+
+impl BroadPhase {
+    pub fn spatial_hash(cell_size: f32) -> Self {
+        BroadPhase::SpatialHash(SpatialHash::new(cell_size))
+    }
+
+    pub fn sweep_and_prune(axis: SweepAxis) -> Self {
+        BroadPhase::SweepAndPrune(SweepAndPrune::new(axis))
+    }
+
+    pub fn dynamic_tree(margin: f32) -> Self {
+        BroadPhase::DynamicTree(TreeBroadPhase::new(margin))
+    }
+
+    // `proxies` holds the AABB of every body with a collider. Pairs come back as indices into it,
+    // lower index first and sorted, so the narrow phase sees them in the same order whatever
+    // broad phase is active.
+    pub fn pairs(&mut self, proxies: &[(BodyHandle, BoundingBox)]) -> Vec<(usize, usize)> {
+        match self {
+            BroadPhase::BruteForce => brute_force_pairs(proxies),
+            BroadPhase::SpatialHash(spatial_hash) => spatial_hash.pairs(proxies),
+            BroadPhase::SweepAndPrune(sweep_and_prune) => sweep_and_prune.pairs(proxies),
+            BroadPhase::DynamicTree(tree) => tree.pairs(proxies),
+        }
+    }
+
+    // Bodies that may overlap `aabb`, judged by where they were the last time `pairs` ran. May
+    // include bodies that do not overlap it, and `None` means every body has to be checked.
+    pub fn query(&self, aabb: &BoundingBox) -> Option<Vec<BodyHandle>> {
+        match self {
+            BroadPhase::BruteForce => None,
+            BroadPhase::SpatialHash(spatial_hash) => Some(spatial_hash.query(aabb)),
+            BroadPhase::SweepAndPrune(sweep_and_prune) => Some(sweep_and_prune.query(aabb)),
+            BroadPhase::DynamicTree(tree) => Some(tree.query(aabb)),
+        }
+    }
+}
+
+pub fn brute_force_pairs(proxies: &[(BodyHandle, BoundingBox)]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (i, (_, aabb_1)) in proxies.iter().enumerate() {
+        for (j, (_, aabb_2)) in proxies.iter().enumerate().skip(i + 1) {
+            if bounding_box_collision(*aabb_1, *aabb_2) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
@@ -1,7 +1,15 @@
 use crate::types::circle::Circle;
 use crate::types::bounding_box::BoundingBox;
+use crate::types::capsule::Capsule;
+use crate::types::chain::{Chain, ChainEdge};
+use crate::types::collider::Collider;
+use crate::types::contact::{Contact, ContactPoint};
+use crate::types::convex_shape::ConvexShape;
+use crate::types::polygon::{Polygon, MAX_POLYGON_VERTICES};
+use crate::types::segment::Segment;
 use crate::types::rigid_body::RigidBody;
 use crate::types::vector_2d::Vector2D;
+use crate::utilities::gjk::{distance, intersects, penetration};
 
 pub fn circle_collision(circle_1: Circle, circle_2: Circle) -> bool {
     let delta = circle_1.center - circle_2.center;
@@ -18,27 +26,1067 @@ pub fn bounding_box_collision(bounding_box_1: BoundingBox, bounding_box_2: Bound
         bounding_box_1.half_height + bounding_box_2.half_height)
 }
 
-pub fn resolve_collision(body_1: &mut RigidBody, body_2: &mut RigidBody, normal: Vector2D, overlap: f32) {
-    let relative_velocity = body_2.velocity - body_1.velocity;
-    let velocity_along_normal = relative_velocity.dot(&normal);
+pub fn body_contacts(body_1: &RigidBody, body_2: &RigidBody) -> Vec<Contact> {
+    match (body_1.world_collider(), body_2.world_collider()) {
+        (Some(shape_1), Some(shape_2)) => shape_contacts(&shape_1, &shape_2),
+        _ => Vec::new(),
+    }
+}
+
+// Yes/no test for sensors. Runs GJK on the convex parts without building any contacts, and stops
+// at the first pair of parts that overlap.
+pub fn body_overlap(body_1: &RigidBody, body_2: &RigidBody) -> bool {
+    match (body_1.world_collider(), body_2.world_collider()) {
+        (Some(shape_1), Some(shape_2)) if bounding_box_collision(shape_1.world_aabb(), shape_2.world_aabb()) => {
+            shapes_overlap(&shape_1, &shape_2)
+        }
+        _ => false,
+    }
+}
+
+// Both shapes must already be in world space. Chain edges count from both sides here.
+fn shapes_overlap(shape_1: &Collider, shape_2: &Collider) -> bool {
+    match (shape_1, shape_2) {
+        (Collider::Compound(children), _) => children.iter().any(|child| shapes_overlap(child, shape_2)),
+        (_, Collider::Compound(children)) => children.iter().any(|child| shapes_overlap(shape_1, child)),
+        (Collider::Chain(chain), _) => chain.edges_near(&shape_2.world_aabb())
+            .into_iter()
+            .any(|index| shapes_overlap(&Collider::Segment(chain.edge(index).segment), shape_2)),
+        (_, Collider::Chain(_)) => shapes_overlap(shape_2, shape_1),
+        _ => match (shape_1.as_convex(), shape_2.as_convex()) {
+            (Some(convex_1), Some(convex_2)) => intersects(convex_1, convex_2),
+            _ => false,
+        },
+    }
+}
+
+// Both shapes must already be in world space. Compound shapes produce one contact per touching
+// pair of children, with the child indices folded into the point ids.
+pub fn shape_contacts(shape_1: &Collider, shape_2: &Collider) -> Vec<Contact> {
+    match (shape_1, shape_2) {
+        (Collider::Compound(children), _) => children.iter()
+            .enumerate()
+            .flat_map(|(i, child)| shape_contacts(child, shape_2)
+                .into_iter()
+                .map(move |contact| contact.with_id_offset((i as u32 + 1) << 16)))
+            .collect(),
+        (_, Collider::Compound(children)) => children.iter()
+            .enumerate()
+            .flat_map(|(i, child)| shape_contacts(shape_1, child)
+                .into_iter()
+                .map(move |contact| contact.with_id_offset((i as u32 + 1) << 8)))
+            .collect(),
+        (Collider::Chain(chain), _) => chain_contacts(chain, shape_2),
+        (_, Collider::Chain(chain)) => chain_contacts(chain, shape_1)
+            .into_iter()
+            .map(|contact| contact.flipped())
+            .collect(),
+        _ => shape_contact(shape_1, shape_2).into_iter().collect(),
+    }
+}
+
+// Single contact between two world-space shapes; for compounds this is the deepest one. Pairs
+// without a dedicated routine below go through GJK/EPA.
+pub fn shape_contact(shape_1: &Collider, shape_2: &Collider) -> Option<Contact> {
+    match (shape_1.as_convex(), shape_2.as_convex()) {
+        (Some(convex_1), Some(convex_2)) => {
+            dedicated_contact(shape_1, shape_2).unwrap_or_else(|| convex_contact(convex_1, convex_2))
+        }
+        _ => shape_contacts(shape_1, shape_2)
+            .into_iter()
+            .max_by(|contact_1, contact_2| contact_1.depth.total_cmp(&contact_2.depth)),
+    }
+}
+
+// Returns `None` for pairs that have no specialised routine.
+fn dedicated_contact(shape_1: &Collider, shape_2: &Collider) -> Option<Option<Contact>> {
+    let contact = match (shape_1, shape_2) {
+        (Collider::Circle(circle_1), Collider::Circle(circle_2)) => circle_circle_contact(*circle_1, *circle_2),
+        (Collider::Box(box_1), Collider::Box(box_2)) => box_box_contact(*box_1, *box_2),
+        (Collider::Circle(circle), Collider::Box(bounding_box)) => circle_box_contact(*circle, *bounding_box),
+        (Collider::Box(bounding_box), Collider::Circle(circle)) => {
+            circle_box_contact(*circle, *bounding_box).map(|contact| contact.flipped())
+        }
+        (Collider::Polygon(polygon_1), Collider::Polygon(polygon_2)) => polygon_polygon_contact(polygon_1, polygon_2),
+        (Collider::Polygon(polygon), Collider::Box(bounding_box)) => {
+            polygon_polygon_contact(polygon, &Polygon::from(*bounding_box))
+        }
+        (Collider::Box(bounding_box), Collider::Polygon(polygon)) => {
+            polygon_polygon_contact(&Polygon::from(*bounding_box), polygon)
+        }
+        (Collider::Polygon(polygon), Collider::Circle(circle)) => polygon_circle_contact(polygon, *circle),
+        (Collider::Circle(circle), Collider::Polygon(polygon)) => {
+            polygon_circle_contact(polygon, *circle).map(|contact| contact.flipped())
+        }
+        // Capsules and segments against the shapes above.
+        (Collider::Box(_) | Collider::Polygon(_), _) => polygon_capsule_contact(&as_polygon(shape_1)?, &as_capsule(shape_2)?),
+        (_, Collider::Box(_) | Collider::Polygon(_)) => {
+            polygon_capsule_contact(&as_polygon(shape_2)?, &as_capsule(shape_1)?).map(|contact| contact.flipped())
+        }
+        _ => capsule_capsule_contact(&as_capsule(shape_1)?, &as_capsule(shape_2)?),
+    };
+    Some(contact)
+}
+
+fn as_polygon(shape: &Collider) -> Option<Polygon> {
+    match shape {
+        Collider::Box(bounding_box) => Some(Polygon::from(*bounding_box)),
+        Collider::Polygon(polygon) => Some(*polygon),
+        _ => None,
+    }
+}
+
+// Circles and segments are capsules with no length or no radius.
+fn as_capsule(shape: &Collider) -> Option<Capsule> {
+    match shape {
+        Collider::Circle(circle) => Some(Capsule::new(circle.center, circle.center, circle.radius)),
+        Collider::Capsule(capsule) => Some(*capsule),
+        Collider::Segment(segment) => Some(Capsule::new(segment.start, segment.end, 0.0)),
+        _ => None,
+    }
+}
+
+// How close to an end of an edge a contact must be to count as touching the corner.
+const CHAIN_CORNER_TOLERANCE: f32 = 0.02;
+
+// Contacts between a chain and a convex shape, normals pointing from the chain to the shape. Each
+// edge is collided as a segment, but only when the shape's center is on the edge's front side,
+// and corner contacts are dropped whenever a neighbouring edge already covers that corner. Edge
+// indices go into the top byte of the point ids.
+pub fn chain_contacts(chain: &Chain, shape: &Collider) -> Vec<Contact> {
+    let Some(convex) = shape.as_convex() else {
+        return Vec::new();
+    };
+    let aabb = shape.world_aabb();
+    let center = convex.center();
+
+    chain.edges_near(&aabb)
+        .into_iter()
+        .filter(|&index| bounding_box_collision(chain.edge_aabb(index), aabb))
+        .filter_map(|index| {
+            let edge = chain.edge(index);
+            if (center - edge.segment.start).dot(&edge.normal) < 0.0 {
+                return None;
+            }
+            let contact = shape_contact(&Collider::Segment(edge.segment), shape)?;
+            if !on_exposed_feature(&edge, &contact) {
+                return None;
+            }
+            Some(contact.with_id_offset((index as u32 % 255 + 1) << 24))
+        })
+        .collect()
+}
+
+// The ghost vertices decide who owns a corner. A corner where the chain is flat or bends inwards
+// is never exposed, since the shape will touch the face of the next edge first. An exposed
+// corner belongs to the edge that starts there, and only for normals past the end of the
+// previous edge.
+fn on_exposed_feature(edge: &ChainEdge, contact: &Contact) -> bool {
+    if contact.normal.dot(&edge.normal) > 0.999 {
+        return true;
+    }
+
+    let deepest = contact.points()
+        .iter()
+        .max_by(|point_1, point_2| point_1.depth.total_cmp(&point_2.depth))
+        .expect("contacts have at least one point");
+    let length = edge.segment.length();
+    let fraction = edge.segment.closest_fraction(deepest.position);
+
+    if fraction * length < CHAIN_CORNER_TOLERANCE {
+        match edge.ghost_before {
+            None => true,
+            Some(ghost) => edge.start_is_convex() && contact.normal.dot(&(edge.segment.start - ghost)) >= 0.0,
+        }
+    }
+    else if (1.0 - fraction) * length < CHAIN_CORNER_TOLERANCE {
+        edge.ghost_after.is_none()
+    }
+    else {
+        contact.normal.dot(&edge.normal) > 0.0
+    }
+}
+
+// Single point contact for any two convex shapes, placed halfway between the deepest points.
+pub fn convex_contact(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape) -> Option<Contact> {
+    let penetration = penetration(shape_1, shape_2)?;
+    let point = (penetration.point_1 + penetration.point_2) * 0.5;
+    Some(Contact::new(penetration.normal, penetration.depth, point))
+}
+
+pub fn circle_circle_contact(circle_1: Circle, circle_2: Circle) -> Option<Contact> {
+    if !circle_collision(circle_1, circle_2) {
+        return None;
+    }
+
+    let delta = circle_2.center - circle_1.center;
+    let distance = delta.length();
+    let radius_sum = circle_1.radius + circle_2.radius;
+
+    // Coincident centers have no meaningful direction, so pick an arbitrary but stable one.
+    let normal = if distance == 0.0 {
+        Vector2D::new(0.0, 1.0)
+    }
+    else {
+        delta / distance
+    };
+    let depth = radius_sum - distance;
+    let point = circle_1.center + normal * (circle_1.radius - depth * 0.5);
+    Some(Contact::new(normal, depth, point))
+}
+
+pub fn box_box_contact(box_1: BoundingBox, box_2: BoundingBox) -> Option<Contact> {
+    if !bounding_box_collision(box_1, box_2) {
+        return None;
+    }
+
+    let delta = box_2.center - box_1.center;
+    let overlap_x = box_1.half_width + box_2.half_width - delta.x.abs();
+    let overlap_y = box_1.half_height + box_2.half_height - delta.y.abs();
+    let min_1 = box_1.min();
+    let max_1 = box_1.max();
+    let min_2 = box_2.min();
+    let max_2 = box_2.max();
+
+    // The contact points span the overlap of the two touching faces.
+    if overlap_x < overlap_y {
+        let direction = if delta.x < 0.0 { -1.0 } else { 1.0 };
+        let face_x = if direction > 0.0 {
+            (max_1.x + min_2.x) * 0.5
+        }
+        else {
+            (min_1.x + max_2.x) * 0.5
+        };
+        let low_y = f32::max(min_1.y, min_2.y);
+        let high_y = f32::min(max_1.y, max_2.y);
+        Some(face_contact(Vector2D::new(direction, 0.0), overlap_x,
+                          Vector2D::new(face_x, low_y), Vector2D::new(face_x, high_y), 0))
+    }
+    else {
+        let direction = if delta.y < 0.0 { -1.0 } else { 1.0 };
+        let face_y = if direction > 0.0 {
+            (max_1.y + min_2.y) * 0.5
+        }
+        else {
+            (min_1.y + max_2.y) * 0.5
+        };
+        let low_x = f32::max(min_1.x, min_2.x);
+        let high_x = f32::min(max_1.x, max_2.x);
+        Some(face_contact(Vector2D::new(0.0, direction), overlap_y,
+                          Vector2D::new(low_x, face_y), Vector2D::new(high_x, face_y), 2))
+    }
+}
+
+fn face_contact(normal: Vector2D, depth: f32, start: Vector2D, end: Vector2D, first_id: u32) -> Contact {
+    let span = end - start;
+    if span.dot(&span) == 0.0 {
+        return Contact::new(normal, depth, start).with_id_offset(first_id);
+    }
+    Contact::with_two_points(
+        normal,
+        ContactPoint::new(start, depth).with_id(first_id),
+        ContactPoint::new(end, depth).with_id(first_id + 1),
+    )
+}
+
+pub fn circle_box_contact(circle: Circle, bounding_box: BoundingBox) -> Option<Contact> {
+    let min = bounding_box.min();
+    let max = bounding_box.max();
+    let closest = Vector2D::new(
+        circle.center.x.clamp(min.x, max.x),
+        circle.center.y.clamp(min.y, max.y),
+    );
+    let delta = closest - circle.center;
+    let squared_distance = delta.dot(&delta);
+
+    if squared_distance > 0.0 {
+        if squared_distance > circle.radius * circle.radius {
+            return None;
+        }
+        let distance = squared_distance.sqrt();
+        return Some(Contact::new(delta / distance, circle.radius - distance, closest));
+    }
+
+    // The center is inside the box, so push the circle out through the nearest face.
+    let local = circle.center - bounding_box.center;
+    let gap_x = bounding_box.half_width - local.x.abs();
+    let gap_y = bounding_box.half_height - local.y.abs();
+    if gap_x < gap_y {
+        let face = if local.x < 0.0 { -1.0 } else { 1.0 };
+        let point = Vector2D::new(bounding_box.center.x + face * bounding_box.half_width, circle.center.y);
+        Some(Contact::new(Vector2D::new(-face, 0.0), circle.radius + gap_x, point))
+    }
+    else {
+        let face = if local.y < 0.0 { -1.0 } else { 1.0 };
+        let point = Vector2D::new(circle.center.x, bounding_box.center.y + face * bounding_box.half_height);
+        Some(Contact::new(Vector2D::new(0.0, -face), circle.radius + gap_y, point))
+    }
+}
+
+// Separating axis test over the edge normals of both polygons. The face with the least
+// penetration becomes the reference face and the most anti-parallel edge of the other polygon is
+// clipped against its side planes, which leaves up to two contact points.
+pub fn polygon_polygon_contact(polygon_1: &Polygon, polygon_2: &Polygon) -> Option<Contact> {
+    let (edge_1, separation_1) = max_separation(polygon_1, polygon_2);
+    if separation_1 > 0.0 {
+        return None;
+    }
+    let (edge_2, separation_2) = max_separation(polygon_2, polygon_1);
+    if separation_2 > 0.0 {
+        return None;
+    }
+
+    // Prefer the first polygon unless the second is clearly better, so the reference face does
+    // not flip back and forth between steps when both are about equal.
+    let flip = separation_2 > separation_1 + 1e-3;
+    let (reference, incident, reference_edge) = if flip {
+        (polygon_2, polygon_1, edge_2)
+    }
+    else {
+        (polygon_1, polygon_2, edge_1)
+    };
+
+    let count = reference.vertex_count();
+    let reference_normal = reference.normals()[reference_edge];
+    let face_start = reference.vertices()[reference_edge];
+    let face_end = reference.vertices()[(reference_edge + 1) % count];
+    // Taken from the normal, since the face of a flat box has no length.
+    let tangent = reference_normal.perpendicular();
+
+    let incident_edge = (0..incident.vertex_count())
+        .min_by(|&i, &j| {
+            incident.normals()[i].dot(&reference_normal).total_cmp(&incident.normals()[j].dot(&reference_normal))
+        })
+        .expect("polygons have vertices");
+    let incident_count = incident.vertex_count();
+    let incident_points = [
+        (incident.vertices()[incident_edge], incident_edge),
+        (incident.vertices()[(incident_edge + 1) % incident_count], (incident_edge + 1) % incident_count),
+    ];
+
+    // Clip the incident edge to the width of the reference face.
+    let clipped = clip_segment(incident_points, tangent * -1.0, -tangent.dot(&face_start))?;
+    let clipped = clip_segment(clipped, tangent, tangent.dot(&face_end))?;
+
+    let normal = if flip { reference_normal * -1.0 } else { reference_normal };
+    let mut points = Vec::with_capacity(2);
+    for (point, vertex) in clipped {
+        let separation = (point - face_start).dot(&reference_normal);
+        if separation <= 0.0 {
+            let depth = -separation;
+            // Halfway between the incident point and the reference face.
+            let position = point + reference_normal * (depth * 0.5);
+            let id = ((flip as usize * MAX_POLYGON_VERTICES + reference_edge) * MAX_POLYGON_VERTICES + vertex) as u32;
+            points.push(ContactPoint::new(position, depth).with_id(id));
+        }
+    }
+
+    match points[..] {
+        [point_1, point_2] => Some(Contact::with_two_points(normal, point_1, point_2)),
+        [point] => Some(Contact::new(normal, point.depth, point.position).with_id_offset(point.id)),
+        _ => None,
+    }
+}
+
+// Edge of `polygon_1` along whose normal the polygons are furthest apart, and that distance.
+fn max_separation(polygon_1: &Polygon, polygon_2: &Polygon) -> (usize, f32) {
+    let mut best = (0, f32::MIN);
+    for (i, (normal, vertex)) in polygon_1.normals().iter().zip(polygon_1.vertices()).enumerate() {
+        let deepest = polygon_2.vertices()[polygon_2.support(*normal * -1.0)];
+        let separation = (deepest - *vertex).dot(normal);
+        if separation > best.1 {
+            best = (i, separation);
+        }
+    }
+    best
+}
+
+// Keeps the part of the segment where `dot(normal, point) <= offset`. The tag of a point created
+// by the clip is taken from the endpoint that was cut off.
+fn clip_segment(segment: [(Vector2D, usize); 2], normal: Vector2D, offset: f32)
+    -> Option<[(Vector2D, usize); 2]> {
+    let distance_1 = normal.dot(&segment[0].0) - offset;
+    let distance_2 = normal.dot(&segment[1].0) - offset;
+
+    match (distance_1 <= 0.0, distance_2 <= 0.0) {
+        (true, true) => Some(segment),
+        (false, false) => None,
+        (inside_1, _) => {
+            let t = distance_1 / (distance_1 - distance_2);
+            let crossing = segment[0].0 + (segment[1].0 - segment[0].0) * t;
+            if inside_1 {
+                Some([segment[0], (crossing, segment[1].1)])
+            }
+            else {
+                Some([(crossing, segment[0].1), segment[1]])
+            }
+        }
+    }
+}
+
+pub fn polygon_circle_contact(polygon: &Polygon, circle: Circle) -> Option<Contact> {
+    let count = polygon.vertex_count();
+    let (edge, separation) = polygon.normals().iter()
+        .zip(polygon.vertices())
+        .map(|(normal, vertex)| (circle.center - *vertex).dot(normal))
+        .enumerate()
+        .max_by(|(_, separation_1), (_, separation_2)| separation_1.total_cmp(separation_2))
+        .expect("polygons have vertices");
+    if separation > circle.radius {
+        return None;
+    }
+
+    let normal = polygon.normals()[edge];
+    let start = polygon.vertices()[edge];
+    let end = polygon.vertices()[(edge + 1) % count];
+
+    // Center inside the polygon: push out through the nearest face.
+    if separation < 0.0 {
+        let point = circle.center - normal * separation;
+        return Some(Contact::new(normal, circle.radius - separation, point).with_id_offset(edge as u32));
+    }
+
+    // Beyond either end of the edge the closest feature is a vertex.
+    let (closest, id) = if (circle.center - start).dot(&(end - start)) <= 0.0 {
+        (start, count + edge)
+    }
+    else if (circle.center - end).dot(&(start - end)) <= 0.0 {
+        (end, count + (edge + 1) % count)
+    }
+    else {
+        (circle.center - normal * separation, edge)
+    };
+
+    let delta = circle.center - closest;
+    let distance = delta.length();
+    if distance > circle.radius {
+        return None;
+    }
+    let normal = if distance > 0.0 { delta / distance } else { normal };
+    Some(Contact::new(normal, circle.radius - distance, closest).with_id_offset(id as u32))
+}
+
+// Two points when the capsules lie side by side, so a capsule resting on another does not rock.
+pub fn capsule_capsule_contact(capsule_1: &Capsule, capsule_2: &Capsule) -> Option<Contact> {
+    let (segment_1, segment_2) = (capsule_1.segment(), capsule_2.segment());
+    let radius = capsule_1.radius + capsule_2.radius;
+    let (closest_1, closest_2) = segment_1.closest_points(&segment_2);
+    let delta = closest_2 - closest_1;
+    let distance = delta.length();
+    if distance > radius {
+        return None;
+    }
+
+    let normal = if distance > f32::EPSILON {
+        delta / distance
+    }
+    else {
+        // Core segments cross: push out sideways from the first one, towards the second.
+        let direction = segment_1.end - segment_1.start;
+        let side = if direction.dot(&direction) > 0.0 { direction.perpendicular() } else { Vector2D::new(0.0, 1.0) };
+        let side = side / side.length();
+        if side.dot(&(capsule_2.center() - capsule_1.center())) < 0.0 { side * -1.0 } else { side }
+    };
+    let surface_point = |point_1: Vector2D, point_2: Vector2D| {
+        (point_1 + normal * capsule_1.radius + point_2 - normal * capsule_2.radius) * 0.5
+    };
+
+    if let Some((start, end)) = parallel_overlap(&segment_1, &segment_2) {
+        let points: Vec<ContactPoint> = [start, end].iter()
+            .enumerate()
+            .filter_map(|(i, point_2)| {
+                let point_1 = segment_1.closest_point(*point_2);
+                let depth = radius - (*point_2 - point_1).dot(&normal);
+                (depth >= 0.0).then(|| ContactPoint::new(surface_point(point_1, *point_2), depth).with_id(i as u32 + 1))
+            })
+            .collect();
+        if let [point_1, point_2] = points[..] {
+            return Some(Contact::with_two_points(normal, point_1, point_2));
+        }
+    }
+    Some(Contact::new(normal, radius - distance, surface_point(closest_1, closest_2)))
+}
+
+// When the segments are close to parallel, the part of `segment_2` that lies alongside
+// `segment_1`.
+fn parallel_overlap(segment_1: &Segment, segment_2: &Segment) -> Option<(Vector2D, Vector2D)> {
+    let (length_1, length_2) = (segment_1.length(), segment_2.length());
+    if length_1 < f32::EPSILON || length_2 < f32::EPSILON {
+        return None;
+    }
+    let direction_1 = (segment_1.end - segment_1.start) / length_1;
+    let direction_2 = (segment_2.end - segment_2.start) / length_2;
+    if direction_1.cross(&direction_2).abs() > 0.05 {
+        return None;
+    }
+
+    let segment = [(segment_2.start, 0), (segment_2.end, 1)];
+    let clipped = clip_segment(segment, direction_1 * -1.0, -direction_1.dot(&segment_1.start))?;
+    let clipped = clip_segment(clipped, direction_1, direction_1.dot(&segment_1.end))?;
+    if (clipped[1].0 - clipped[0].0).length() < f32::EPSILON {
+        return None;
+    }
+    Some((clipped[0].0, clipped[1].0))
+}
+
+// The capsule's core segment is clipped against the polygon face it rests on, giving two points
+// when it lies flat on that face.
+pub fn polygon_capsule_contact(polygon: &Polygon, capsule: &Capsule) -> Option<Contact> {
+    let segment = capsule.segment();
+    let closest = distance(polygon, &segment);
+    if closest.distance > capsule.radius {
+        return None;
+    }
+    // The core itself is inside the polygon, which needs the full penetration search.
+    if closest.distance < 1e-4 {
+        return convex_contact(polygon, capsule);
+    }
+
+    let normal = (closest.point_2 - closest.point_1) / closest.distance;
+    let count = polygon.vertex_count();
+    let face = (0..count)
+        .max_by(|&i, &j| polygon.normals()[i].dot(&normal).total_cmp(&polygon.normals()[j].dot(&normal)))
+        .expect("polygons have vertices");
+    let face_normal = polygon.normals()[face];
+    let (face_start, face_end) = (polygon.vertices()[face], polygon.vertices()[(face + 1) % count]);
+    let tangent = (face_end - face_start) / (face_end - face_start).length();
+
+    let lies_flat = face_normal.dot(&normal) > 0.99 && segment.length() > f32::EPSILON &&
+        ((segment.end - segment.start) / segment.length()).dot(&face_normal).abs() < 0.05;
+    if lies_flat {
+        let clipped = clip_segment([(segment.start, 0), (segment.end, 1)], tangent * -1.0, -tangent.dot(&face_start))
+            .and_then(|clipped| clip_segment(clipped, tangent, tangent.dot(&face_end)));
+        if let Some(clipped) = clipped {
+            let points: Vec<ContactPoint> = clipped.iter()
+                .filter_map(|(point, end)| {
+                    let separation = (*point - face_start).dot(&face_normal);
+                    let depth = capsule.radius - separation;
+                    let position = *point - face_normal * (separation + capsule.radius) * 0.5;
+                    let id = (face * 2 + end) as u32 + 1;
+                    (depth >= 0.0).then(|| ContactPoint::new(position, depth).with_id(id))
+                })
+                .collect();
+            if let [point_1, point_2] = points[..] {
+                return Some(Contact::with_two_points(face_normal, point_1, point_2));
+            }
+        }
+    }
+
+    let position = (closest.point_1 + closest.point_2 - normal * capsule.radius) * 0.5;
+    Some(Contact::new(normal, capsule.radius - closest.distance, position))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_with(collider: Collider, position: Vector2D) -> RigidBody {
+        RigidBody::new(1.0, position, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(collider)
+    }
 
-    if velocity_along_normal > 0.0 {
-        return;
+    #[test]
+    fn test_circle_collision() {
+        let circle_1 = Circle::new(Vector2D::new(0.0, 0.0), 1.0);
+        let circle_2 = Circle::new(Vector2D::new(1.5, 0.0), 1.0);
+        let circle_3 = Circle::new(Vector2D::new(3.0, 0.0), 1.0);
+        assert!(circle_collision(circle_1, circle_2));
+        assert!(!circle_collision(circle_1, circle_3));
     }
 
-    let restitution = 0.5;
+    #[test]
+    fn test_bounding_box_collision() {
+        let bounding_box_1 = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+        let bounding_box_2 = BoundingBox::new(Vector2D::new(1.5, 1.5), 1.0, 1.0);
+        let bounding_box_3 = BoundingBox::new(Vector2D::new(2.5, 0.0), 1.0, 1.0);
+        assert!(bounding_box_collision(bounding_box_1, bounding_box_2));
+        assert!(!bounding_box_collision(bounding_box_1, bounding_box_3));
+    }
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn test_circle_circle_contact_touching() {
+        let circle_1 = Circle::new(Vector2D::new(0.0, 0.0), 1.0);
+        let circle_2 = Circle::new(Vector2D::new(2.0, 0.0), 1.0);
+
+        let contact = circle_circle_contact(circle_1, circle_2).unwrap();
+        assert_eq!(contact.normal.x, 1.0);
+        assert_eq!(contact.normal.y, 0.0);
+        assert_eq!(contact.depth, 0.0);
+        assert_eq!(contact.point_count, 1);
+        assert!(approx_eq(contact.points[0].position.x, 1.0));
+    }
+
+    #[test]
+    fn test_circle_circle_contact_separated() {
+        let circle_1 = Circle::new(Vector2D::new(0.0, 0.0), 1.0);
+        let circle_2 = Circle::new(Vector2D::new(2.1, 0.0), 1.0);
+        assert!(circle_circle_contact(circle_1, circle_2).is_none());
+    }
+
+    #[test]
+    fn test_circle_circle_contact_deep_penetration() {
+        let circle_1 = Circle::new(Vector2D::new(0.0, 0.0), 2.0);
+        let circle_2 = Circle::new(Vector2D::new(0.0, -0.5), 1.0);
+
+        let contact = circle_circle_contact(circle_1, circle_2).unwrap();
+        assert_eq!(contact.normal.x, 0.0);
+        assert_eq!(contact.normal.y, -1.0);
+        assert!(approx_eq(contact.depth, 2.5));
+        assert!(approx_eq(contact.points[0].position.y, -0.75));
+    }
+
+    #[test]
+    fn test_circle_circle_contact_coincident_centers() {
+        let circle_1 = Circle::new(Vector2D::new(1.0, 1.0), 1.0);
+        let circle_2 = Circle::new(Vector2D::new(1.0, 1.0), 0.5);
+
+        let contact = circle_circle_contact(circle_1, circle_2).unwrap();
+        assert!(approx_eq(contact.normal.length(), 1.0));
+        assert!(approx_eq(contact.depth, 1.5));
+        assert!(contact.points[0].position.x.is_finite());
+    }
+
+    #[test]
+    fn test_box_box_contact_touching() {
+        let box_1 = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+        let box_2 = BoundingBox::new(Vector2D::new(2.0, 0.5), 1.0, 1.0);
+
+        let contact = box_box_contact(box_1, box_2).unwrap();
+        assert_eq!(contact.normal.x, 1.0);
+        assert_eq!(contact.depth, 0.0);
+        assert_eq!(contact.point_count, 2);
+        assert!(approx_eq(contact.points[0].position.x, 1.0));
+        assert!(approx_eq(contact.points[0].position.y, -0.5));
+        assert!(approx_eq(contact.points[1].position.y, 1.0));
+    }
+
+    #[test]
+    fn test_box_box_contact_resting_on_top() {
+        let ground = BoundingBox::new(Vector2D::new(0.0, -1.0), 10.0, 1.0);
+        let crate_box = BoundingBox::new(Vector2D::new(3.0, 0.4), 0.5, 0.5);
+
+        let contact = box_box_contact(ground, crate_box).unwrap();
+        assert_eq!(contact.normal.x, 0.0);
+        assert_eq!(contact.normal.y, 1.0);
+        assert!(approx_eq(contact.depth, 0.1));
+        assert_eq!(contact.points().len(), 2);
+        assert!(approx_eq(contact.points[0].position.x, 2.5));
+        assert!(approx_eq(contact.points[1].position.x, 3.5));
+    }
+
+    #[test]
+    fn test_box_box_contact_deep_penetration() {
+        let box_1 = BoundingBox::new(Vector2D::new(0.0, 0.0), 2.0, 2.0);
+        let box_2 = BoundingBox::new(Vector2D::new(-0.5, 0.2), 1.0, 1.0);
+
+        let contact = box_box_contact(box_1, box_2).unwrap();
+        assert_eq!(contact.normal.x, -1.0);
+        assert!(approx_eq(contact.depth, 2.5));
+    }
+
+    #[test]
+    fn test_box_box_contact_coincident_centers() {
+        let box_1 = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+        let box_2 = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+
+        let contact = box_box_contact(box_1, box_2).unwrap();
+        assert!(approx_eq(contact.normal.length(), 1.0));
+        assert!(approx_eq(contact.depth, 2.0));
+    }
+
+    #[test]
+    fn test_circle_box_contact_touching() {
+        let circle = Circle::new(Vector2D::new(0.0, 2.0), 1.0);
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+
+        let contact = circle_box_contact(circle, bounding_box).unwrap();
+        assert_eq!(contact.normal.y, -1.0);
+        assert_eq!(contact.depth, 0.0);
+        assert!(approx_eq(contact.points[0].position.y, 1.0));
+    }
+
+    #[test]
+    fn test_circle_box_contact_corner() {
+        let circle = Circle::new(Vector2D::new(1.5, 1.5), 1.0);
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
 
-    let mut impulse_scalar = -(1.0 + restitution) * velocity_along_normal;
-    impulse_scalar /= body_1.inv_mass + body_2.inv_mass;
+        let contact = circle_box_contact(circle, bounding_box).unwrap();
+        assert!(approx_eq(contact.normal.x, -f32::sqrt(0.5)));
+        assert!(approx_eq(contact.normal.y, -f32::sqrt(0.5)));
+        assert!(approx_eq(contact.depth, 1.0 - f32::sqrt(0.5)));
+        assert!(approx_eq(contact.points[0].position.x, 1.0));
+        assert!(approx_eq(contact.points[0].position.y, 1.0));
+    }
+
+    #[test]
+    fn test_circle_box_contact_separated() {
+        let circle = Circle::new(Vector2D::new(1.8, 1.8), 1.0);
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+        assert!(circle_box_contact(circle, bounding_box).is_none());
+    }
+
+    #[test]
+    fn test_circle_box_contact_deep_penetration() {
+        let circle = Circle::new(Vector2D::new(0.8, 0.0), 0.5);
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 2.0);
+
+        let contact = circle_box_contact(circle, bounding_box).unwrap();
+        assert_eq!(contact.normal.x, -1.0);
+        assert_eq!(contact.normal.y, 0.0);
+        assert!(approx_eq(contact.depth, 0.7));
+        assert!(approx_eq(contact.points[0].position.x, 1.0));
+    }
+
+    #[test]
+    fn test_circle_box_contact_coincident_centers() {
+        let circle = Circle::new(Vector2D::new(0.0, 0.0), 0.5);
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 2.0, 1.0);
+
+        let contact = circle_box_contact(circle, bounding_box).unwrap();
+        assert!(approx_eq(contact.normal.length(), 1.0));
+        assert_eq!(contact.normal.x, 0.0);
+        assert!(approx_eq(contact.depth, 1.5));
+    }
+
+    #[test]
+    fn test_shape_contact_box_circle_is_flipped() {
+        let circle = Collider::Circle(Circle::new(Vector2D::new(0.0, 1.5), 1.0));
+        let bounding_box = Collider::Box(BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0));
+
+        let contact = shape_contact(&bounding_box, &circle).unwrap();
+        assert_eq!(contact.normal.y, 1.0);
+        assert!(approx_eq(contact.depth, 0.5));
+    }
+
+    #[test]
+    fn test_body_contacts_uses_collider_offset() {
+        let body_1 = body_with(Collider::Circle(Circle::new(Vector2D::new(2.0, 0.0), 1.0)), Vector2D::new(0.0, 0.0));
+        let body_2 = body_with(Collider::circle(1.0), Vector2D::new(3.5, 0.0));
+
+        let contact = body_contacts(&body_1, &body_2)[0];
+        assert_eq!(contact.normal.x, 1.0);
+        assert!(approx_eq(contact.depth, 0.5));
+    }
+
+    #[test]
+    fn test_body_contacts_compound() {
+        let dumbbell = Collider::Compound(vec![
+            Collider::Circle(Circle::new(Vector2D::new(-2.0, 0.0), 1.0)),
+            Collider::Circle(Circle::new(Vector2D::new(2.0, 0.0), 1.0)),
+        ]);
+        let body_1 = body_with(dumbbell, Vector2D::new(0.0, 1.9));
+        let body_2 = body_with(Collider::rectangle(5.0, 1.0), Vector2D::new(0.0, 0.0));
+
+        let contacts = body_contacts(&body_1, &body_2);
+        assert_eq!(contacts.len(), 2);
+        assert_ne!(contacts[0].points[0].id, contacts[1].points[0].id);
+        for contact in &contacts {
+            assert_eq!(contact.normal.y, -1.0);
+            assert!(approx_eq(contact.depth, 0.1));
+        }
+    }
+
+    #[test]
+    fn test_shape_contact_compound_picks_deepest() {
+        let compound = Collider::Compound(vec![
+            Collider::Circle(Circle::new(Vector2D::new(-1.0, 0.0), 1.0)),
+            Collider::Circle(Circle::new(Vector2D::new(1.0, 0.5), 1.0)),
+        ]);
+        let ground = Collider::Box(BoundingBox::new(Vector2D::new(0.0, -1.5), 5.0, 1.0));
+
+        let contact = shape_contact(&compound, &ground).unwrap();
+        assert!(approx_eq(contact.depth, 0.5));
+    }
+
+    fn square_at(x: f32, y: f32, angle: f32) -> Polygon {
+        Polygon::rectangle(0.5, 0.5).transformed(Vector2D::new(x, y), angle)
+    }
+
+    #[test]
+    fn test_polygon_polygon_contact_resting_face() {
+        let ground = Polygon::rectangle(10.0, 1.0).transformed(Vector2D::new(0.0, -1.0), 0.0);
+        let crate_polygon = square_at(3.0, 0.4, 0.0);
+
+        let contact = polygon_polygon_contact(&ground, &crate_polygon).unwrap();
+        assert!(approx_eq(contact.normal.x, 0.0));
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert!(approx_eq(contact.depth, 0.1));
+        assert_eq!(contact.point_count, 2);
+        let mut xs: Vec<f32> = contact.points().iter().map(|point| point.position.x).collect();
+        xs.sort_by(f32::total_cmp);
+        assert!(approx_eq(xs[0], 2.5));
+        assert!(approx_eq(xs[1], 3.5));
+        assert!(contact.points().iter().all(|point| approx_eq(point.position.y, -0.05)));
+        assert_ne!(contact.points[0].id, contact.points[1].id);
+    }
+
+    #[test]
+    fn test_polygon_polygon_contact_normal_points_from_first_to_second() {
+        // The small square's face is the reference here, so the normal has to be flipped.
+        let small = square_at(0.0, 0.9, 0.0);
+        let large = Polygon::rectangle(3.0, 0.5).transformed(Vector2D::new(0.0, 0.0), 0.0);
+
+        let contact = polygon_polygon_contact(&small, &large).unwrap();
+        assert!(approx_eq(contact.normal.y, -1.0));
+        assert!(approx_eq(contact.depth, 0.1));
+        assert_eq!(contact.point_count, 2);
+    }
 
-    let impulse = normal * impulse_scalar;
-    body_1.velocity = body_1.velocity - (impulse * body_1.inv_mass);
-    body_2.velocity = body_2.velocity - (impulse * body_2.inv_mass);
+    #[test]
+    fn test_polygon_polygon_contact_rotated_corner() {
+        let ground = Polygon::rectangle(10.0, 1.0).transformed(Vector2D::new(0.0, -1.0), 0.0);
+        let half_diagonal = f32::sqrt(0.5);
+        let diamond = square_at(1.0, half_diagonal - 0.05, std::f32::consts::FRAC_PI_4);
 
-    let percentage = 0.8;
-    let allowable_overlap = 0.01;
-    let overlap_correction = normal * (f32::max(overlap - allowable_overlap, 0.0) /
-        (body_1.inv_mass + body_2.inv_mass) * percentage);
-    body_1.position = body_1.position - overlap_correction * body_1.inv_mass;
-    body_2.position = body_2.position + overlap_correction * body_2.inv_mass;
+        let contact = polygon_polygon_contact(&ground, &diamond).unwrap();
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert_eq!(contact.point_count, 1);
+        assert!(approx_eq(contact.depth, 0.05));
+        assert!(approx_eq(contact.points[0].position.x, 1.0));
+    }
+
+    #[test]
+    fn test_polygon_polygon_contact_separated() {
+        assert!(polygon_polygon_contact(&square_at(0.0, 0.0, 0.0), &square_at(1.1, 0.0, 0.0)).is_none());
+        // Axis-aligned boxes would overlap, but the rotated square only reaches 0.5 * sqrt(2).
+        assert!(polygon_polygon_contact(&square_at(0.0, 0.0, 0.0), &square_at(1.2, 1.2, 0.3)).is_none());
+    }
+
+    #[test]
+    fn test_polygon_circle_contact_face_vertex_and_inside() {
+        let square = square_at(0.0, 0.0, 0.0);
+
+        let face = polygon_circle_contact(&square, Circle::new(Vector2D::new(0.2, 1.0), 0.6)).unwrap();
+        assert!(approx_eq(face.normal.y, 1.0));
+        assert!(approx_eq(face.depth, 0.1));
+        assert!(approx_eq(face.points[0].position.x, 0.2));
+        assert!(approx_eq(face.points[0].position.y, 0.5));
+
+        let vertex = polygon_circle_contact(&square, Circle::new(Vector2D::new(1.0, 1.0), 0.8)).unwrap();
+        assert!(approx_eq(vertex.normal.x, f32::sqrt(0.5)));
+        assert!(approx_eq(vertex.depth, 0.8 - f32::sqrt(0.5)));
+        assert!(approx_eq(vertex.points[0].position.x, 0.5));
+
+        let inside = polygon_circle_contact(&square, Circle::new(Vector2D::new(-0.3, 0.1), 0.25)).unwrap();
+        assert!(approx_eq(inside.normal.x, -1.0));
+        assert!(approx_eq(inside.depth, 0.45));
+
+        assert!(polygon_circle_contact(&square, Circle::new(Vector2D::new(1.0, 1.0), 0.7)).is_none());
+        assert_ne!(face.points[0].id, vertex.points[0].id);
+    }
+
+    #[test]
+    fn test_shape_contact_polygon_with_box_and_circle() {
+        let polygon = Collider::Polygon(square_at(0.0, 0.0, 0.0));
+        let bounding_box = Collider::Box(BoundingBox::new(Vector2D::new(0.0, -0.9), 1.0, 0.5));
+        let circle = Collider::Circle(Circle::new(Vector2D::new(0.9, 0.0), 0.5));
+
+        let contact = shape_contact(&bounding_box, &polygon).unwrap();
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert_eq!(contact.point_count, 2);
+
+        let contact = shape_contact(&circle, &polygon).unwrap();
+        assert!(approx_eq(contact.normal.x, -1.0));
+        assert!(approx_eq(contact.depth, 0.1));
+    }
+
+    #[test]
+    fn test_convex_contact_agrees_with_dedicated_routines() {
+        let ground = BoundingBox::new(Vector2D::new(0.0, -1.0), 10.0, 1.0);
+        let pairs = [
+            (Collider::Box(ground), Collider::Circle(Circle::new(Vector2D::new(0.3, 0.4), 0.5))),
+            (Collider::Box(ground), Collider::Box(BoundingBox::new(Vector2D::new(-2.0, 0.3), 0.5, 0.5))),
+            (Collider::Box(ground), Collider::Polygon(square_at(1.0, 0.45, 0.0))),
+            (Collider::Polygon(square_at(0.0, 0.0, 0.2)), Collider::Circle(Circle::new(Vector2D::new(0.8, 0.1), 0.4))),
+        ];
+
+        for (shape_1, shape_2) in pairs {
+            let dedicated = dedicated_contact(&shape_1, &shape_2).unwrap().unwrap();
+            let convex = convex_contact(shape_1.as_convex().unwrap(), shape_2.as_convex().unwrap()).unwrap();
+            assert!((dedicated.depth - convex.depth).abs() < 1e-3, "{:?} vs {:?}", dedicated, convex);
+            assert!(dedicated.normal.dot(&convex.normal) > 0.999, "{:?} vs {:?}", dedicated, convex);
+        }
+    }
+
+    #[test]
+    fn test_capsule_lying_on_box_has_two_points() {
+        let ground = Polygon::from(BoundingBox::new(Vector2D::new(0.0, -1.0), 10.0, 1.0));
+        let capsule = Capsule::new(Vector2D::new(-1.0, 0.4), Vector2D::new(1.0, 0.4), 0.5);
+
+        let contact = polygon_capsule_contact(&ground, &capsule).unwrap();
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert_eq!(contact.point_count, 2);
+        assert!(approx_eq(contact.depth, 0.1));
+        let mut xs: Vec<f32> = contact.points().iter().map(|point| point.position.x).collect();
+        xs.sort_by(f32::total_cmp);
+        assert!(approx_eq(xs[0], -1.0) && approx_eq(xs[1], 1.0));
+        assert!(approx_eq(contact.points[0].position.y, -0.05));
+    }
+
+    #[test]
+    fn test_capsule_standing_on_box_has_one_point() {
+        let ground = Polygon::from(BoundingBox::new(Vector2D::new(0.0, -1.0), 10.0, 1.0));
+        let capsule = Capsule::new(Vector2D::new(2.0, 0.45), Vector2D::new(2.0, 2.0), 0.5);
+
+        let contact = polygon_capsule_contact(&ground, &capsule).unwrap();
+        assert_eq!(contact.point_count, 1);
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert!(approx_eq(contact.depth, 0.05));
+        assert!(approx_eq(contact.points[0].position.x, 2.0));
+
+        let above = Capsule::new(Vector2D::new(2.0, 0.6), Vector2D::new(2.0, 2.0), 0.5);
+        assert!(polygon_capsule_contact(&ground, &above).is_none());
+    }
+
+    #[test]
+    fn test_capsule_core_inside_polygon() {
+        let block = Polygon::from(BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0));
+        let capsule = Capsule::new(Vector2D::new(0.8, -0.2), Vector2D::new(0.8, 0.2), 0.5);
+
+        let contact = polygon_capsule_contact(&block, &capsule).unwrap();
+        assert!(approx_eq(contact.normal.x, 1.0));
+        assert!(approx_eq(contact.depth, 0.7));
+    }
+
+    #[test]
+    fn test_flat_box_contacts_polygons_and_capsules() {
+        let plank = Collider::rectangle(1.0, 0.0);
+        let shapes = [
+            Collider::Polygon(Polygon::rectangle(0.5, 0.5)).translated(Vector2D::new(0.2, 0.45)),
+            Collider::capsule(0.5, 0.25).translated(Vector2D::new(0.2, 0.2)),
+        ];
+        for shape in shapes {
+            let contact = shape_contact(&plank, &shape).unwrap();
+            assert!(f32::abs(contact.normal.y - 1.0) < 1e-4, "Normal {:?} against {:?}", contact.normal, shape);
+            assert!(contact.depth > 0.0);
+        }
+
+        // Even a box squashed to a point still finds the body on top of it.
+        let block = Collider::Polygon(Polygon::rectangle(0.5, 0.5)).translated(Vector2D::new(0.0, 0.45));
+        let contact = shape_contact(&Collider::rectangle(0.0, 0.0), &block).unwrap();
+        assert!(contact.normal.length() > 0.99);
+    }
+
+    #[test]
+    fn test_capsule_capsule_contact() {
+        let lower = Capsule::new(Vector2D::new(-2.0, 0.0), Vector2D::new(2.0, 0.0), 0.5);
+
+        let parallel = Capsule::new(Vector2D::new(1.0, 0.9), Vector2D::new(3.0, 0.9), 0.5);
+        let contact = capsule_capsule_contact(&lower, &parallel).unwrap();
+        assert_eq!(contact.point_count, 2);
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert!(approx_eq(contact.depth, 0.1));
+        let mut xs: Vec<f32> = contact.points().iter().map(|point| point.position.x).collect();
+        xs.sort_by(f32::total_cmp);
+        assert!(approx_eq(xs[0], 1.0) && approx_eq(xs[1], 2.0));
+
+        let crossing = Capsule::new(Vector2D::new(0.5, 0.7), Vector2D::new(0.5, 3.0), 0.25);
+        let contact = capsule_capsule_contact(&lower, &crossing).unwrap();
+        assert_eq!(contact.point_count, 1);
+        assert!(approx_eq(contact.depth, 0.05));
+
+        let apart = Capsule::new(Vector2D::new(0.0, 1.5), Vector2D::new(1.0, 1.5), 0.5);
+        assert!(capsule_capsule_contact(&lower, &apart).is_none());
+    }
+
+    #[test]
+    fn test_shape_contact_circle_segment_and_capsule() {
+        let edge = Collider::Segment(Segment::new(Vector2D::new(-5.0, 0.0), Vector2D::new(5.0, 0.0)));
+        let ball = Collider::Circle(Circle::new(Vector2D::new(1.0, 0.4), 0.5));
+        let capsule = Collider::Capsule(Capsule::new(Vector2D::new(1.0, 0.3), Vector2D::new(1.0, 2.0), 0.5));
+
+        let contact = shape_contact(&ball, &edge).unwrap();
+        assert!(approx_eq(contact.normal.y, -1.0));
+        assert!(approx_eq(contact.depth, 0.1));
+
+        let contact = shape_contact(&edge, &capsule).unwrap();
+        assert!(approx_eq(contact.normal.y, 1.0));
+        assert!(approx_eq(contact.depth, 0.2));
+
+        let above_ball = Collider::Capsule(Capsule::new(Vector2D::new(1.0, 1.0), Vector2D::new(1.0, 2.0), 0.5));
+        let contact = shape_contact(&above_ball, &ball).unwrap();
+        assert!(approx_eq(contact.normal.y, -1.0));
+        assert!(approx_eq(contact.depth, 0.4));
+    }
+
+    fn flat_chain() -> Chain {
+        let points: Vec<Vector2D> = (0..=4).map(|i| Vector2D::new(2.0 - i as f32, 0.0)).collect();
+        Chain::open(&points).unwrap()
+    }
+
+    #[test]
+    fn test_chain_contacts_smooth_over_joints() {
+        let chain = flat_chain();
+        let ball = Collider::Circle(Circle::new(Vector2D::new(0.05, 0.45), 0.5));
+
+        let contacts = chain_contacts(&chain, &ball);
+        assert_eq!(contacts.len(), 1);
+        assert!(approx_eq(contacts[0].normal.y, 1.0));
+        assert!(approx_eq(contacts[0].depth, 0.05));
+
+        // A box whose corner has sunk just below the next edge must not be pushed back sideways.
+        let crate_shape = Collider::Polygon(square_at(0.49, 0.49, 0.0));
+        let contacts = chain_contacts(&chain, &crate_shape);
+        assert!(!contacts.is_empty());
+        for contact in &contacts {
+            assert!(contact.normal.y > 0.999, "Snagged with normal {:?}", contact.normal);
+        }
+    }
+
+    #[test]
+    fn test_chain_contacts_are_one_sided() {
+        let chain = flat_chain();
+
+        let below = Collider::Circle(Circle::new(Vector2D::new(0.5, -0.3), 0.5));
+        assert!(chain_contacts(&chain, &below).is_empty());
+
+        let above = Collider::Circle(Circle::new(Vector2D::new(0.5, 0.3), 0.5));
+        let contact = shape_contact(&above, &Collider::Chain(chain)).unwrap();
+        assert!(approx_eq(contact.normal.y, -1.0));
+    }
+
+    #[test]
+    fn test_chain_contacts_keep_exposed_corners() {
+        // Top of a step going down to the right, and the open end of the chain on the left.
+        let chain = Chain::open(&[
+            Vector2D::new(2.0, -1.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(-1.0, 0.0),
+        ]).unwrap();
+
+        let over_step = Collider::Circle(Circle::new(Vector2D::new(1.25, 0.35), 0.5));
+        let contacts = chain_contacts(&chain, &over_step);
+        assert_eq!(contacts.len(), 1);
+        assert!(approx_eq(contacts[0].normal.x, 0.25 / 0.35f32.hypot(0.25)));
+
+        let past_end = Collider::Circle(Circle::new(Vector2D::new(-1.3, 0.3), 0.5));
+        let contacts = chain_contacts(&chain, &past_end);
+        assert_eq!(contacts.len(), 1);
+        assert!(contacts[0].normal.x < -0.5);
+    }
+
+    #[test]
+    fn test_body_contacts_requires_colliders() {
+        let body_1 = body_with(Collider::circle(1.0), Vector2D::new(0.0, 0.0));
+        let body_2 = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0));
+        assert!(body_contacts(&body_1, &body_2).is_empty());
+    }
+
+    #[test]
+    fn test_body_overlap() {
+        let zone = body_with(Collider::Polygon(Polygon::rectangle(2.0, 1.0)), Vector2D::new(0.0, 0.0));
+        let inside = body_with(Collider::circle(0.2), Vector2D::new(0.5, 0.0));
+        let touching = body_with(Collider::capsule(1.0, 0.25), Vector2D::new(0.0, 1.2));
+        let outside = body_with(Collider::circle(0.2), Vector2D::new(0.0, 2.0));
+
+        assert!(body_overlap(&zone, &inside));
+        assert!(body_overlap(&touching, &zone));
+        assert!(!body_overlap(&zone, &outside));
+        assert!(!body_overlap(&zone, &RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))));
+    }
 }
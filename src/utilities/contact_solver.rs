@@ -0,0 +1,732 @@
+use std::collections::HashMap;
+
+use crate::types::contact::Contact;
+use crate::types::pre_solve_contact::ContactProperties;
+use crate::types::rigid_body::RigidBody;
+use crate::types::solver_settings::SolverSettings;
+use crate::types::vector_2d::Vector2D;
+use crate::types::world::{BodyHandle, World};
+use crate::utilities::math_operations::inverse;
+
+// Identifies one contact point between two bodies across steps.
+pub type ContactKey = (BodyHandle, BodyHandle, u32);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CachedImpulse {
+    pub normal_impulse: f32,
+    pub tangent_impulse: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConstraintPoint {
+    id: u32,
+    // Anchors relative to each body position, in the body's unrotated frame.
+    local_anchor_1: Vector2D,
+    local_anchor_2: Vector2D,
+    depth: f32,
+    normal_mass: f32,
+    tangent_mass: f32,
+    velocity_bias: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContactConstraint {
+    pub body_1: BodyHandle,
+    pub body_2: BodyHandle,
+    pub normal: Vector2D,
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    pub tangent_speed: f32,
+    points: Vec<ConstraintPoint>,
+}
+
+impl ContactConstraint {
+    pub fn normal_impulse(&self) -> f32 {
+        self.points.iter().map(|point| point.normal_impulse).sum()
+    }
+
+    pub fn tangent_impulse(&self) -> f32 {
+        self.points.iter().map(|point| point.tangent_impulse).sum()
+    }
+
+    // World positions of the contact points, following `body_1` as it is now.
+    pub fn world_points<'a>(&'a self, body_1: &'a RigidBody) -> impl Iterator<Item = Vector2D> + 'a {
+        self.points.iter().map(|point| body_1.position + point.local_anchor_1.rotated(body_1.angle))
+    }
+}
+
+// Sequential impulse solver over every contact of a step. Impulses are accumulated per point
+// and clamped as totals, and the previous step's totals are applied up front (warm starting)
+// so stacks converge over several steps instead of being solved from scratch each time.
+pub fn solve_contacts(world: &mut World, contacts: &[(BodyHandle, BodyHandle, Contact, ContactProperties)],
+                      cache: &HashMap<ContactKey, CachedImpulse>, settings: &SolverSettings)
+    -> Vec<ContactConstraint> {
+    let mut constraints: Vec<ContactConstraint> = contacts.iter()
+        .filter_map(|(handle_1, handle_2, contact, properties)| {
+            let (body_1, body_2) = world.get_pair_mut(*handle_1, *handle_2)?;
+            Some(build_constraint(*handle_1, *handle_2, body_1, body_2, contact, properties, cache, settings))
+        })
+        .collect();
+
+    if settings.warm_starting {
+        for constraint in &constraints {
+            if let Some((body_1, body_2)) = world.get_pair_mut(constraint.body_1, constraint.body_2) {
+                warm_start(constraint, body_1, body_2);
+            }
+        }
+    }
+
+    for _ in 0..settings.velocity_iterations {
+        for constraint in &mut constraints {
+            if let Some((body_1, body_2)) = world.get_pair_mut(constraint.body_1, constraint.body_2) {
+                solve_velocity(constraint, body_1, body_2);
+            }
+        }
+    }
+
+    for _ in 0..settings.position_iterations {
+        let mut max_penetration: f32 = 0.0;
+        for constraint in &constraints {
+            if let Some((body_1, body_2)) = world.get_pair_mut(constraint.body_1, constraint.body_2) {
+                max_penetration = max_penetration.max(solve_position(constraint, body_1, body_2, settings));
+            }
+        }
+        if max_penetration <= settings.slop {
+            break;
+        }
+    }
+
+    constraints
+}
+
+pub fn impulse_cache(constraints: &[ContactConstraint]) -> HashMap<ContactKey, CachedImpulse> {
+    constraints.iter()
+        .flat_map(|constraint| constraint.points.iter().map(move |point| (
+            (constraint.body_1, constraint.body_2, point.id),
+            CachedImpulse {
+                normal_impulse: point.normal_impulse,
+                tangent_impulse: point.tangent_impulse,
+            },
+        )))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_constraint(handle_1: BodyHandle, handle_2: BodyHandle, body_1: &RigidBody, body_2: &RigidBody,
+                    contact: &Contact, properties: &ContactProperties,
+                    cache: &HashMap<ContactKey, CachedImpulse>, settings: &SolverSettings) -> ContactConstraint {
+    let normal = contact.normal;
+    let tangent = normal.perpendicular() * -1.0;
+    let restitution = properties.restitution;
+
+    let points = contact.points().iter().map(|contact_point| {
+        let point = contact_point.position;
+        let r_1 = point - body_1.position;
+        let r_2 = point - body_2.position;
+
+        let relative_velocity = body_2.velocity_at_point(point) - body_1.velocity_at_point(point);
+        let velocity_along_normal = relative_velocity.dot(&normal);
+        let velocity_bias = if -velocity_along_normal > settings.restitution_threshold {
+            -restitution * velocity_along_normal
+        }
+        else {
+            0.0
+        };
+
+        let cached = if settings.warm_starting {
+            cache.get(&(handle_1, handle_2, contact_point.id)).copied().unwrap_or_default()
+        }
+        else {
+            CachedImpulse::default()
+        };
+
+        ConstraintPoint {
+            id: contact_point.id,
+            local_anchor_1: r_1.rotated(-body_1.angle),
+            local_anchor_2: r_2.rotated(-body_2.angle),
+            depth: contact_point.depth,
+            normal_mass: inverse(effective_inv_mass(body_1, body_2, r_1, r_2, normal)),
+            tangent_mass: inverse(effective_inv_mass(body_1, body_2, r_1, r_2, tangent)),
+            velocity_bias,
+            normal_impulse: cached.normal_impulse,
+            tangent_impulse: cached.tangent_impulse,
+        }
+    }).collect();
+
+    ContactConstraint {
+        body_1: handle_1,
+        body_2: handle_2,
+        normal,
+        static_friction: properties.static_friction,
+        dynamic_friction: properties.dynamic_friction,
+        tangent_speed: properties.tangent_speed,
+        points,
+    }
+}
+
+fn warm_start(constraint: &ContactConstraint, body_1: &mut RigidBody, body_2: &mut RigidBody) {
+    let tangent = constraint.normal.perpendicular() * -1.0;
+    for point in &constraint.points {
+        let impulse = constraint.normal * point.normal_impulse + tangent * point.tangent_impulse;
+        apply_impulse(body_1, body_2, point, impulse);
+    }
+}
+
+fn solve_velocity(constraint: &mut ContactConstraint, body_1: &mut RigidBody, body_2: &mut RigidBody) {
+    let normal = constraint.normal;
+    let tangent = normal.perpendicular() * -1.0;
+
+    // Friction first, limited by the normal impulse from the previous iteration. Each point sticks
+    // while the impulse holding it fits in the static cone and slides on the dynamic one otherwise.
+    for point in &mut constraint.points {
+        let relative_velocity = relative_velocity(body_1, body_2, point);
+        let lambda = -(relative_velocity.dot(&tangent) - constraint.tangent_speed) * point.tangent_mass;
+        let sticking_impulse = point.tangent_impulse + lambda;
+        let new_impulse = if sticking_impulse.abs() <= constraint.static_friction * point.normal_impulse {
+            sticking_impulse
+        }
+        else {
+            let max_friction = constraint.dynamic_friction * point.normal_impulse;
+            sticking_impulse.clamp(-max_friction, max_friction)
+        };
+        let applied = new_impulse - point.tangent_impulse;
+        point.tangent_impulse = new_impulse;
+        apply_impulse(body_1, body_2, point, tangent * applied);
+    }
+
+    if constraint.points.len() == 2 && solve_normal_block(constraint, body_1, body_2) {
+        return;
+    }
+
+    for point in &mut constraint.points {
+        let relative_velocity = relative_velocity(body_1, body_2, point);
+        let lambda = -(relative_velocity.dot(&normal) - point.velocity_bias) * point.normal_mass;
+        let new_impulse = f32::max(point.normal_impulse + lambda, 0.0);
+        let applied = new_impulse - point.normal_impulse;
+        point.normal_impulse = new_impulse;
+        apply_impulse(body_1, body_2, point, normal * applied);
+    }
+}
+
+// Solves both normal impulses of a two-point manifold together as a small LCP. Solving them one
+// after the other makes flat resting contacts rock. Returns false when the 2x2 system is too
+// badly conditioned, in which case the points are solved one at a time instead.
+fn solve_normal_block(constraint: &mut ContactConstraint, body_1: &mut RigidBody, body_2: &mut RigidBody) -> bool {
+    let normal = constraint.normal;
+    let (point_1, point_2) = (constraint.points[0], constraint.points[1]);
+    let (k_11, k_12, k_22) = block_mass_matrix(constraint, body_1, body_2);
+
+    let accumulated = (point_1.normal_impulse, point_2.normal_impulse);
+    let vn_1 = relative_velocity(body_1, body_2, &point_1).dot(&normal);
+    let vn_2 = relative_velocity(body_1, body_2, &point_2).dot(&normal);
+    // Written in terms of the new total impulse rather than the increment.
+    let b_1 = vn_1 - point_1.velocity_bias - (k_11 * accumulated.0 + k_12 * accumulated.1);
+    let b_2 = vn_2 - point_2.velocity_bias - (k_12 * accumulated.0 + k_22 * accumulated.1);
+
+    let Some(solution) = block_lcp(k_11, k_12, k_22, b_1, b_2) else {
+        return false;
+    };
+
+    constraint.points[0].normal_impulse = solution.0;
+    constraint.points[1].normal_impulse = solution.1;
+    apply_impulse(body_1, body_2, &point_1, normal * (solution.0 - accumulated.0));
+    apply_impulse(body_1, body_2, &point_2, normal * (solution.1 - accumulated.1));
+    true
+}
+
+fn block_mass_matrix(constraint: &ContactConstraint, body_1: &RigidBody, body_2: &RigidBody) -> (f32, f32, f32) {
+    let normal = constraint.normal;
+    let (point_1, point_2) = (&constraint.points[0], &constraint.points[1]);
+    let rn_1a = point_1.local_anchor_1.rotated(body_1.angle).cross(&normal);
+    let rn_1b = point_1.local_anchor_2.rotated(body_2.angle).cross(&normal);
+    let rn_2a = point_2.local_anchor_1.rotated(body_1.angle).cross(&normal);
+    let rn_2b = point_2.local_anchor_2.rotated(body_2.angle).cross(&normal);
+
+    let inv_mass = body_1.inv_mass + body_2.inv_mass;
+    (
+        inv_mass + body_1.inv_inertia * rn_1a * rn_1a + body_2.inv_inertia * rn_1b * rn_1b,
+        inv_mass + body_1.inv_inertia * rn_1a * rn_2a + body_2.inv_inertia * rn_1b * rn_2b,
+        inv_mass + body_1.inv_inertia * rn_2a * rn_2a + body_2.inv_inertia * rn_2b * rn_2b,
+    )
+}
+
+// Finds x >= 0 with K x + b >= 0 and x . (K x + b) = 0 by trying each case in turn: both points
+// active, only one of them, or neither.
+fn block_lcp(k_11: f32, k_12: f32, k_22: f32, b_1: f32, b_2: f32) -> Option<(f32, f32)> {
+    let determinant = k_11 * k_22 - k_12 * k_12;
+    let max_condition = 1000.0;
+    if k_11 == 0.0 || k_22 == 0.0 || k_11 * k_11 >= max_condition * determinant {
+        return None;
+    }
+
+    let both = (
+        -(k_22 * b_1 - k_12 * b_2) / determinant,
+        -(-k_12 * b_1 + k_11 * b_2) / determinant,
+    );
+    if both.0 >= 0.0 && both.1 >= 0.0 {
+        return Some(both);
+    }
+    let only_first = -b_1 / k_11;
+    if only_first >= 0.0 && k_12 * only_first + b_2 >= 0.0 {
+        return Some((only_first, 0.0));
+    }
+    let only_second = -b_2 / k_22;
+    if only_second >= 0.0 && k_12 * only_second + b_1 >= 0.0 {
+        return Some((0.0, only_second));
+    }
+    if b_1 >= 0.0 && b_2 >= 0.0 {
+        return Some((0.0, 0.0));
+    }
+    None
+}
+
+// Pushes the bodies apart along the normal and returns the penetration that was left.
+fn solve_position(constraint: &ContactConstraint, body_1: &mut RigidBody, body_2: &mut RigidBody,
+                  settings: &SolverSettings) -> f32 {
+    let normal = constraint.normal;
+    let mut max_penetration: f32 = 0.0;
+
+    let targets: Vec<(Vector2D, Vector2D, f32)> = constraint.points.iter().map(|point| {
+        let r_1 = point.local_anchor_1.rotated(body_1.angle);
+        let r_2 = point.local_anchor_2.rotated(body_2.angle);
+        // Both anchors started on the same world point, so their drift measures the separation.
+        let separation = ((body_2.position + r_2) - (body_1.position + r_1)).dot(&normal) - point.depth;
+        max_penetration = max_penetration.max(-separation);
+        (r_1, r_2, settings.correction_factor * f32::min(separation + settings.slop, 0.0))
+    }).collect();
+
+    // Like the velocity pass, two-point manifolds are corrected together so they stay level.
+    let magnitudes: Vec<f32> = if targets.len() == 2 {
+        let (k_11, k_12, k_22) = block_mass_matrix(constraint, body_1, body_2);
+        match block_lcp(k_11, k_12, k_22, targets[0].2, targets[1].2) {
+            Some((x_1, x_2)) => vec![x_1, x_2],
+            None => single_point_corrections(&targets, body_1, body_2, normal),
+        }
+    }
+    else {
+        single_point_corrections(&targets, body_1, body_2, normal)
+    };
+
+    for ((r_1, r_2, _), magnitude) in targets.iter().zip(magnitudes) {
+        let impulse = normal * magnitude;
+        body_1.position = body_1.position - impulse * body_1.inv_mass;
+        body_1.angle -= r_1.cross(&impulse) * body_1.inv_inertia;
+        body_2.position = body_2.position + impulse * body_2.inv_mass;
+        body_2.angle += r_2.cross(&impulse) * body_2.inv_inertia;
+    }
+    max_penetration
+}
+
+fn single_point_corrections(targets: &[(Vector2D, Vector2D, f32)], body_1: &RigidBody, body_2: &RigidBody,
+                            normal: Vector2D) -> Vec<f32> {
+    // Split between the points so several points on one side do not overcorrect.
+    let share = 1.0 / targets.len() as f32;
+    targets.iter()
+        .map(|(r_1, r_2, correction)| {
+            -correction * inverse(effective_inv_mass(body_1, body_2, *r_1, *r_2, normal)) * share
+        })
+        .collect()
+}
+
+fn relative_velocity(body_1: &RigidBody, body_2: &RigidBody, point: &ConstraintPoint) -> Vector2D {
+    let r_1 = point.local_anchor_1.rotated(body_1.angle);
+    let r_2 = point.local_anchor_2.rotated(body_2.angle);
+    (body_2.velocity + r_2.perpendicular() * body_2.angular_velocity) -
+        (body_1.velocity + r_1.perpendicular() * body_1.angular_velocity)
+}
+
+fn apply_impulse(body_1: &mut RigidBody, body_2: &mut RigidBody, point: &ConstraintPoint, impulse: Vector2D) {
+    let r_1 = point.local_anchor_1.rotated(body_1.angle);
+    let r_2 = point.local_anchor_2.rotated(body_2.angle);
+    body_1.velocity = body_1.velocity - impulse * body_1.inv_mass;
+    body_1.angular_velocity -= r_1.cross(&impulse) * body_1.inv_inertia;
+    body_2.velocity = body_2.velocity + impulse * body_2.inv_mass;
+    body_2.angular_velocity += r_2.cross(&impulse) * body_2.inv_inertia;
+}
+
+fn effective_inv_mass(body_1: &RigidBody, body_2: &RigidBody, r_1: Vector2D, r_2: Vector2D,
+                      direction: Vector2D) -> f32 {
+    let r_1_cross = r_1.cross(&direction);
+    let r_2_cross = r_2.cross(&direction);
+    body_1.inv_mass + body_2.inv_mass +
+        r_1_cross * r_1_cross * body_1.inv_inertia +
+        r_2_cross * r_2_cross * body_2.inv_inertia
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::collider::Collider;
+    use crate::types::material::Material;
+
+    fn stack_world(height: usize, settings: SolverSettings) -> (World, Vec<BodyHandle>) {
+        let mut world = World::new();
+        world.solver_settings = settings;
+        let material = Material::new(1.0, 0.0, 0.6, 0.4);
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)).with_material(material));
+
+        let handles = (0..height).map(|i| {
+            world.insert(RigidBody::from_material(
+                Collider::rectangle(0.5, 0.5),
+                material,
+                Vector2D::new(0.0, 0.5 + i as f32 * 1.02)
+            ))
+        }).collect();
+        (world, handles)
+    }
+
+    fn max_speed(world: &World, handles: &[BodyHandle]) -> f32 {
+        handles.iter()
+            .map(|handle| world.get(*handle).unwrap().velocity.length())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn test_stack_comes_to_rest() {
+        let (mut world, handles) = stack_world(5, SolverSettings::default());
+
+        for _ in 0..240 {
+            world.step(1.0 / 60.0);
+        }
+
+        assert!(max_speed(&world, &handles) < 0.05, "Stack still moving at {}", max_speed(&world, &handles));
+        // Every contact keeps about the slop worth of overlap, so check the spacing per level.
+        let mut below = 0.0;
+        for (i, handle) in handles.iter().enumerate() {
+            let body = world.get(*handle).unwrap();
+            let gap = body.position.y - below;
+            let expected = if i == 0 { 0.5 } else { 1.0 };
+            assert!(f32::abs(gap - expected) < 3.0 * world.solver_settings.slop,
+                    "Box {} settled at {}", i, body.position.y);
+            below = body.position.y;
+            assert!(body.position.x.abs() < 0.01);
+            assert!(body.angle.abs() < 0.01, "Box {} turned by {}", i, body.angle);
+        }
+    }
+
+    #[test]
+    fn test_warm_starting_caches_resting_impulse() {
+        let (mut world, handles) = stack_world(1, SolverSettings::default());
+        let dt = 1.0 / 60.0;
+
+        for _ in 0..120 {
+            world.step(dt);
+        }
+
+        // A resting box needs exactly its weight per step from the ground.
+        let body = world.get(handles[0]).unwrap();
+        let total_impulse: f32 = world.contact_cache.values().map(|cached| cached.normal_impulse).sum();
+        assert_eq!(world.contact_cache.len(), 2);
+        assert!(f32::abs(total_impulse - body.mass * 9.81 * dt) < 1e-3 * body.mass,
+                "Cached impulse {} for weight {}", total_impulse, body.mass * 9.81 * dt);
+    }
+
+    #[test]
+    fn test_warm_starting_reduces_jitter() {
+        let settle = |warm_starting: bool| {
+            let settings = SolverSettings { warm_starting, velocity_iterations: 2, ..SolverSettings::default() };
+            let (mut world, handles) = stack_world(6, settings);
+            for _ in 0..180 {
+                world.step(1.0 / 60.0);
+            }
+            max_speed(&world, &handles)
+        };
+
+        let warm = settle(true);
+        let cold = settle(false);
+        assert!(warm < cold, "Warm started stack moving at {}, cold at {}", warm, cold);
+    }
+
+    #[test]
+    fn test_accumulated_normal_impulse_is_never_negative() {
+        let (mut world, _) = stack_world(3, SolverSettings::default());
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            assert!(world.contact_cache.values().all(|cached| cached.normal_impulse >= 0.0));
+        }
+    }
+
+    #[test]
+    fn test_friction_impulse_is_clamped_by_normal_impulse() {
+        let (mut world, handles) = stack_world(1, SolverSettings::default());
+        world.get_mut(handles[0]).unwrap().velocity = Vector2D::new(5.0, 0.0);
+
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+            for cached in world.contact_cache.values() {
+                assert!(cached.tangent_impulse.abs() <= 0.6 * cached.normal_impulse + 1e-6);
+            }
+        }
+    }
+
+    fn ground(world: &mut World, material: Material) {
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -0.5),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(50.0, 0.5)).with_material(material));
+    }
+
+    fn bounce_speed(impact_speed: f32) -> f32 {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let bouncy = Material::new(1.0, 1.0, 0.0, 0.0);
+        ground(&mut world, bouncy);
+        let mut ball = RigidBody::from_material(Collider::circle(0.5), bouncy, Vector2D::new(0.0, 0.5 + 0.001));
+        ball.velocity = Vector2D::new(0.0, -impact_speed);
+        let handle = world.insert(ball);
+
+        world.step(1.0 / 60.0);
+        world.get(handle).unwrap().velocity.y
+    }
+
+    #[test]
+    fn test_restitution_reflects_closing_speed() {
+        assert!(f32::abs(bounce_speed(4.0) - 4.0) < 1e-3);
+    }
+
+    #[test]
+    fn test_slow_impacts_below_restitution_threshold_do_not_bounce() {
+        assert!(bounce_speed(0.4).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_head_on_impact_conserves_momentum() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let material = Material::new(1.0, 0.5, 0.0, 0.0);
+        let mut insert_ball = |mass: f32, x: f32, speed: f32| {
+            world.insert(RigidBody::new(mass, Vector2D::new(x, 0.0), Vector2D::new(speed, 0.0), Vector2D::new(0.0, 0.0))
+                .with_collider(Collider::circle(0.5))
+                .with_material(material))
+        };
+        let light = insert_ball(1.0, 0.0, 3.0);
+        let heavy = insert_ball(3.0, 1.02, -1.0);
+
+        world.step(1.0 / 60.0);
+
+        let (light, heavy) = (world.get(light).unwrap(), world.get(heavy).unwrap());
+        let momentum = light.velocity * light.mass + heavy.velocity * heavy.mass;
+        assert!(f32::abs(momentum.x) < 1e-4, "Momentum changed to {}", momentum.x);
+        assert!(momentum.y.abs() < 1e-4);
+        // Separation speed is restitution times the closing speed of 4.
+        assert!(f32::abs(heavy.velocity.x - light.velocity.x - 2.0) < 1e-4);
+    }
+
+    fn ball(mass: f32, position: Vector2D, velocity: Vector2D, restitution: f32) -> RigidBody {
+        RigidBody::new(mass, position, velocity, Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::circle(1.0))
+            .with_material(Material::new(1.0, restitution, 0.0, 0.0))
+    }
+
+    fn kinetic_energy(bodies: &[&RigidBody]) -> f32 {
+        bodies.iter().map(|body| {
+            0.5 * body.mass * body.velocity.dot(&body.velocity) +
+                0.5 * body.inertia * body.angular_velocity * body.angular_velocity
+        }).sum()
+    }
+
+    #[test]
+    fn test_glancing_impact_conserves_momentum_and_energy() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let body_1 = world.insert(ball(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(2.0, 0.5), 1.0));
+        let body_2 = world.insert(ball(1.5, Vector2D::new(1.5, 1.2), Vector2D::new(0.0, 0.0), 1.0));
+        let (before_1, before_2) = (world.get(body_1).unwrap().clone(), world.get(body_2).unwrap().clone());
+        let momentum_before = before_1.velocity * before_1.mass + before_2.velocity * before_2.mass;
+        let energy_before = kinetic_energy(&[&before_1, &before_2]);
+
+        world.step(1.0 / 60.0);
+
+        let (body_1, body_2) = (world.get(body_1).unwrap(), world.get(body_2).unwrap());
+        let momentum_after = body_1.velocity * body_1.mass + body_2.velocity * body_2.mass;
+        assert!((momentum_after - momentum_before).length() < 1e-4, "Momentum {:?} became {:?}", momentum_before, momentum_after);
+        let energy_after = kinetic_energy(&[body_1, body_2]);
+        assert!(energy_after <= energy_before + 1e-4, "Energy grew from {} to {}", energy_before, energy_after);
+        // The struck ball leaves along the line of centers, nowhere near the striker's direction.
+        let mut line_of_centers = body_2.position - body_1.position;
+        line_of_centers.normalize();
+        assert!(body_2.velocity.cross(&line_of_centers).abs() < 0.05, "Struck ball moving {:?}", body_2.velocity);
+        assert!(body_2.velocity.dot(&line_of_centers) > 0.0);
+    }
+
+    #[test]
+    fn test_impact_separates_bodies() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let body_1 = world.insert(ball(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0), 0.5));
+        let body_2 = world.insert(ball(1.0, Vector2D::new(1.5, 0.0), Vector2D::new(0.0, 0.0), 0.5));
+
+        world.step(1.0 / 60.0);
+
+        let (body_1, body_2) = (world.get(body_1).unwrap(), world.get(body_2).unwrap());
+        assert!(body_2.velocity.x > body_1.velocity.x);
+        assert!(body_2.position.x - body_1.position.x > 1.5);
+    }
+
+    #[test]
+    fn test_elastic_head_on_impact_conserves_energy() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let body_1 = world.insert(ball(2.0, Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0), 1.0));
+        let body_2 = world.insert(ball(2.0, Vector2D::new(1.99, 0.0), Vector2D::new(0.0, 0.0), 1.0));
+        let energy_before = kinetic_energy(&[world.get(body_1).unwrap(), world.get(body_2).unwrap()]);
+
+        world.step(1.0 / 60.0);
+
+        // Equal masses swap velocities.
+        let (body_1, body_2) = (world.get(body_1).unwrap(), world.get(body_2).unwrap());
+        assert!(body_1.velocity.x.abs() < 1e-4, "Left moving at {}", body_1.velocity.x);
+        assert!(f32::abs(body_2.velocity.x - 1.0) < 1e-4);
+        assert!(f32::abs(kinetic_energy(&[body_1, body_2]) - energy_before) < 1e-4);
+    }
+
+    #[test]
+    fn test_static_body_does_not_move() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let wall = world.insert(RigidBody::new(0.0, Vector2D::new(2.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(0.5, 5.0))
+            .with_material(Material::new(1.0, 1.0, 0.0, 0.0)));
+        let body = world.insert(ball(1.0, Vector2D::new(0.6, 0.0), Vector2D::new(4.0, 0.0), 1.0));
+
+        world.step(1.0 / 60.0);
+
+        let (wall, body) = (world.get(wall).unwrap(), world.get(body).unwrap());
+        assert_eq!(wall.velocity.x, 0.0);
+        assert_eq!(wall.position.x, 2.0);
+        assert!(f32::abs(body.velocity.x + 4.0) < 1e-4, "Bounced at {}", body.velocity.x);
+        assert!(body.position.x < 0.6);
+    }
+
+    #[test]
+    fn test_restitution_threshold_between_moving_bodies() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        world.solver_settings = SolverSettings { restitution_threshold: 1.0, ..SolverSettings::default() };
+        let body_1 = world.insert(ball(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.5, 0.0), 1.0));
+        let body_2 = world.insert(ball(1.0, Vector2D::new(1.99, 0.0), Vector2D::new(0.0, 0.0), 1.0));
+
+        world.step(1.0 / 60.0);
+
+        // Closing at 0.5, below the threshold, so they move on together.
+        assert!(f32::abs(world.get(body_1).unwrap().velocity.x - 0.25) < 1e-4);
+        assert!(f32::abs(world.get(body_2).unwrap().velocity.x - 0.25) < 1e-4);
+    }
+
+    #[test]
+    fn test_positional_correction_settings() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        world.solver_settings = SolverSettings {
+            correction_factor: 0.5,
+            slop: 0.1,
+            position_iterations: 1,
+            ..SolverSettings::default()
+        };
+        let body_1 = world.insert(ball(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), 0.0));
+        let body_2 = world.insert(ball(1.0, Vector2D::new(1.5, 0.0), Vector2D::new(0.0, 0.0), 0.0));
+
+        world.step(1.0 / 60.0);
+
+        // (0.5 overlap - 0.1 slop) * 0.5 split evenly between equal masses.
+        assert!(f32::abs(world.get(body_1).unwrap().position.x + 0.1) < 1e-4);
+        assert!(f32::abs(world.get(body_2).unwrap().position.x - 1.6) < 1e-4);
+    }
+
+    // Drops `falling`, resting its bottom on y = 0, onto a ledge that only holds up its right
+    // half.
+    fn drop_on_ledge(falling: RigidBody) -> RigidBody {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let material = Material::new(1.0, 0.5, 0.0, 0.0);
+        world.insert(RigidBody::new(0.0, Vector2D::new(1.75, -0.5), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(1.25, 0.5))
+            .with_material(material));
+        let mut falling = falling.with_material(material);
+        falling.velocity = Vector2D::new(0.0, -2.0);
+        let handle = world.insert(falling);
+
+        world.step(1.0 / 60.0);
+        world.get(handle).unwrap().clone()
+    }
+
+    #[test]
+    fn test_off_center_impact_spins() {
+        let body = drop_on_ledge(RigidBody::from_collider(Collider::rectangle(1.0, 0.5), 1.0, Vector2D::new(0.0, 0.5)));
+        assert!(body.angular_velocity > 0.5, "Expected counter-clockwise spin, got {}", body.angular_velocity);
+        assert!(body.velocity.y > -2.0);
+    }
+
+    #[test]
+    fn test_impact_without_inertia_does_not_spin() {
+        let body = drop_on_ledge(RigidBody::new(1.0, Vector2D::new(0.0, 0.5), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(1.0, 0.5)));
+        assert_eq!(body.angular_velocity, 0.0);
+        assert!(f32::abs(body.velocity.y - 1.0) < 1e-4, "Bounced at {}", body.velocity.y);
+    }
+
+    #[test]
+    fn test_central_impact_does_not_spin() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let material = Material::new(1.0, 0.5, 0.5, 0.5);
+        let mut insert_ball = |x: f32, speed: f32| {
+            let mut ball = RigidBody::from_material(Collider::circle(0.5), material, Vector2D::new(x, 0.0));
+            ball.velocity = Vector2D::new(speed, 0.0);
+            world.insert(ball)
+        };
+        let body_1 = insert_ball(0.0, 1.0);
+        let body_2 = insert_ball(0.95, 0.0);
+
+        world.step(1.0 / 60.0);
+
+        for handle in [body_1, body_2] {
+            let body = world.get(handle).unwrap();
+            assert!(body.inertia > 0.0);
+            assert!(body.angular_velocity.abs() < 1e-5, "Spinning at {}", body.angular_velocity);
+        }
+        assert!(world.get(body_2).unwrap().velocity.x > 0.5);
+    }
+
+    fn slide(material: Material, speed: f32, steps: usize) -> f32 {
+        let mut world = World::new();
+        ground(&mut world, material);
+        let mut sliding = RigidBody::from_material(Collider::rectangle(0.5, 0.5), material, Vector2D::new(0.0, 0.5));
+        sliding.velocity = Vector2D::new(speed, 0.0);
+        let handle = world.insert(sliding);
+
+        for _ in 0..steps {
+            world.step(1.0 / 60.0);
+        }
+        world.get(handle).unwrap().velocity.x
+    }
+
+    #[test]
+    fn test_static_friction_holds_slow_contact() {
+        // The first step has no warm started normal impulse to hold on to yet.
+        let speed = slide(Material::new(1.0, 0.0, 1.0, 0.8), 0.05, 3);
+        assert!(speed.abs() < 1e-4, "Box sliding at {}", speed);
+    }
+
+    #[test]
+    fn test_frictionless_slide_keeps_tangential_speed() {
+        let speed = slide(Material::new(1.0, 0.0, 0.0, 0.0), 5.0, 1);
+        assert!(f32::abs(speed - 5.0) < 1e-5, "Box sliding at {}", speed);
+    }
+
+    #[test]
+    fn test_dynamic_friction_slows_slide() {
+        // The normal impulse carries the weight for one step, so friction takes 0.1 * g * dt off.
+        let speed = slide(Material::new(1.0, 0.0, 0.1, 0.1), 5.0, 1);
+        assert!(f32::abs(speed - (5.0 - 0.1 * 9.81 / 60.0)) < 1e-3, "Box sliding at {}", speed);
+    }
+
+    #[test]
+    fn test_sliding_uses_dynamic_friction() {
+        // Well past the static cone, so every point slides on the lower dynamic coefficient.
+        let speed = slide(Material::new(1.0, 0.0, 0.9, 0.1), 5.0, 1);
+        assert!(f32::abs(speed - (5.0 - 0.1 * 9.81 / 60.0)) < 1e-3, "Box sliding at {}", speed);
+    }
+}
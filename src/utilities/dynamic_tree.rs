@@ -0,0 +1,676 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::bounding_box::BoundingBox;
+use crate::types::vector_2d::Vector2D;
+use crate::types::world::BodyHandle;
+use crate::utilities::collisions::bounding_box_collision;
+
+// Extra room given to every leaf so small movements do not touch the tree at all.
+const DEFAULT_MARGIN: f32 = 0.1;
+// How far ahead of a moving proxy its fat AABB is stretched, in steps of displacement.
+const DISPLACEMENT_MULTIPLIER: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProxyId(usize);
+
+#[derive(Debug, Clone)]
+struct Node {
+    aabb: BoundingBox,
+    parent: Option<usize>,
+    children: Option<(usize, usize)>,
+    // Leaves have height 0 and free nodes -1.
+    height: i32,
+    handle: Option<BodyHandle>,
+}
+
+// Bounding volume hierarchy over fattened AABBs. Leaves are placed with the surface area
+// heuristic (perimeter, in 2D) and the tree is kept balanced with AVL style rotations, so queries
+// stay logarithmic no matter how differently sized the bodies are.
+#[derive(Debug, Clone)]
+pub struct DynamicTree {
+    pub margin: f32,
+    nodes: Vec<Node>,
+    free_nodes: Vec<usize>,
+    root: Option<usize>,
+    proxy_count: usize,
+}
+
+impl Default for DynamicTree {
+    fn default() -> Self {
+        DynamicTree::new(DEFAULT_MARGIN)
+    }
+}
+
+impl DynamicTree {
+    pub fn new(margin: f32) -> Self {
+        DynamicTree {
+            margin,
+            nodes: Vec::new(),
+            free_nodes: Vec::new(),
+            root: None,
+            proxy_count: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.proxy_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proxy_count == 0
+    }
+
+    // Zero for a single leaf, -1 for an empty tree.
+    pub fn height(&self) -> i32 {
+        self.root.map_or(-1, |root| self.nodes[root].height)
+    }
+
+    pub fn insert(&mut self, handle: BodyHandle, aabb: BoundingBox) -> ProxyId {
+        let leaf = self.allocate(Node {
+            aabb: aabb.expanded(self.margin),
+            parent: None,
+            children: None,
+            height: 0,
+            handle: Some(handle),
+        });
+        self.insert_leaf(leaf);
+        self.proxy_count += 1;
+        ProxyId(leaf)
+    }
+
+    pub fn remove(&mut self, proxy: ProxyId) -> Option<BodyHandle> {
+        let handle = self.nodes.get(proxy.0)?.handle?;
+        self.remove_leaf(proxy.0);
+        self.release(proxy.0);
+        self.proxy_count -= 1;
+        Some(handle)
+    }
+
+    // Only touches the tree when `aabb` has left the fat AABB. The new fat AABB is stretched
+    // along `displacement` so a body moving steadily is not reinserted every step. Returns true
+    // when the proxy was reinserted.
+    pub fn move_proxy(&mut self, proxy: ProxyId, aabb: BoundingBox, displacement: Vector2D) -> bool {
+        if self.nodes[proxy.0].aabb.contains(&aabb) {
+            return false;
+        }
+
+        self.remove_leaf(proxy.0);
+        let fat = aabb.expanded(self.margin);
+        let predicted = BoundingBox::new(
+            fat.center + displacement * DISPLACEMENT_MULTIPLIER,
+            fat.half_width,
+            fat.half_height,
+        );
+        self.nodes[proxy.0].aabb = fat.union(&predicted);
+        self.insert_leaf(proxy.0);
+        true
+    }
+
+    pub fn fat_aabb(&self, proxy: ProxyId) -> BoundingBox {
+        self.nodes[proxy.0].aabb
+    }
+
+    pub fn handle(&self, proxy: ProxyId) -> Option<BodyHandle> {
+        self.nodes.get(proxy.0)?.handle
+    }
+
+    // Calls `callback` for every proxy whose fat AABB overlaps `aabb`. Returning false from the
+    // callback ends the query.
+    pub fn query_aabb<F: FnMut(ProxyId) -> bool>(&self, aabb: &BoundingBox, mut callback: F) {
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !bounding_box_collision(node.aabb, *aabb) {
+                continue;
+            }
+            match node.children {
+                Some((child_1, child_2)) => {
+                    stack.push(child_1);
+                    stack.push(child_2);
+                }
+                None => {
+                    if !callback(ProxyId(index)) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    // Calls `callback` for every proxy whose fat AABB the segment from `origin` to `end` passes
+    // through, along with the fraction of the segment still being searched. The callback returns
+    // the fraction to clip the segment to: return the one it was given to keep going, a smaller
+    // one once it has found a hit, or zero to stop.
+    pub fn raycast<F: FnMut(ProxyId, f32) -> f32>(&self, origin: Vector2D, end: Vector2D, mut callback: F) {
+        let delta = end - origin;
+        let mut max_fraction: f32 = 1.0;
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.aabb.raycast(origin, delta, max_fraction).is_none() {
+                continue;
+            }
+            match node.children {
+                Some((child_1, child_2)) => {
+                    stack.push(child_1);
+                    stack.push(child_2);
+                }
+                None => {
+                    max_fraction = max_fraction.min(callback(ProxyId(index), max_fraction));
+                    if max_fraction <= 0.0 {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        if let Some(index) = self.free_nodes.pop() {
+            self.nodes[index] = node;
+            return index;
+        }
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn release(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        node.parent = None;
+        node.children = None;
+        node.height = -1;
+        node.handle = None;
+        self.free_nodes.push(index);
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let Some(root) = self.root else {
+            self.root = Some(leaf);
+            self.nodes[leaf].parent = None;
+            return;
+        };
+
+        // Walk down towards the sibling that grows the total perimeter least.
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = root;
+        while let Some((child_1, child_2)) = self.nodes[index].children {
+            let perimeter = self.nodes[index].aabb.perimeter();
+            let combined_perimeter = self.nodes[index].aabb.union(&leaf_aabb).perimeter();
+
+            // Cost of making a new parent for this node and the leaf.
+            let cost = 2.0 * combined_perimeter;
+            // Every ancestor grows by the same amount whichever child we descend into.
+            let inheritance_cost = 2.0 * (combined_perimeter - perimeter);
+
+            let descend_cost = |child: usize| {
+                let node = &self.nodes[child];
+                let enlarged = node.aabb.union(&leaf_aabb).perimeter();
+                if node.children.is_none() {
+                    enlarged + inheritance_cost
+                }
+                else {
+                    enlarged - node.aabb.perimeter() + inheritance_cost
+                }
+            };
+            let (cost_1, cost_2) = (descend_cost(child_1), descend_cost(child_2));
+
+            if cost < cost_1 && cost < cost_2 {
+                break;
+            }
+            index = if cost_1 < cost_2 { child_1 } else { child_2 };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate(Node {
+            aabb: self.nodes[sibling].aabb.union(&leaf_aabb),
+            parent: old_parent,
+            children: Some((sibling, leaf)),
+            height: self.nodes[sibling].height + 1,
+            handle: None,
+        });
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+        self.replace_child(old_parent, sibling, new_parent);
+
+        self.refit_from(self.nodes[leaf].parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if self.root == Some(leaf) {
+            self.root = None;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent.expect("non-root leaf has a parent");
+        let grandparent = self.nodes[parent].parent;
+        let (child_1, child_2) = self.nodes[parent].children.expect("parent has children");
+        let sibling = if child_1 == leaf { child_2 } else { child_1 };
+
+        self.nodes[sibling].parent = grandparent;
+        self.replace_child(grandparent, parent, sibling);
+        self.release(parent);
+        self.nodes[leaf].parent = None;
+
+        self.refit_from(grandparent);
+    }
+
+    // Points whatever referred to `old` (a parent's child link, or the root) at `new`.
+    fn replace_child(&mut self, parent: Option<usize>, old: usize, new: usize) {
+        match parent {
+            Some(parent) => {
+                let children = self.nodes[parent].children.as_mut().expect("parent has children");
+                if children.0 == old {
+                    children.0 = new;
+                }
+                else {
+                    children.1 = new;
+                }
+            }
+            None => self.root = Some(new),
+        }
+    }
+
+    // Rebalances and refits every node from `index` up to the root.
+    fn refit_from(&mut self, mut index: Option<usize>) {
+        while let Some(current) = index {
+            let current = self.balance(current);
+            let (child_1, child_2) = self.nodes[current].children.expect("inner node has children");
+            self.refit(current, child_1, child_2);
+            index = self.nodes[current].parent;
+        }
+    }
+
+    fn refit(&mut self, index: usize, child_1: usize, child_2: usize) {
+        self.nodes[index].aabb = self.nodes[child_1].aabb.union(&self.nodes[child_2].aabb);
+        self.nodes[index].height = 1 + self.nodes[child_1].height.max(self.nodes[child_2].height);
+    }
+
+    // If one subtree of `a` is more than one level taller than the other, rotates its root up
+    // into `a`'s place. Returns the index of the node now at that position.
+    fn balance(&mut self, a: usize) -> usize {
+        let Some((b, c)) = self.nodes[a].children else {
+            return a;
+        };
+        if self.nodes[a].height < 2 {
+            return a;
+        }
+
+        let balance = self.nodes[c].height - self.nodes[b].height;
+        if balance > 1 {
+            self.rotate_up(a, c, b)
+        }
+        else if balance < -1 {
+            self.rotate_up(a, b, c)
+        }
+        else {
+            a
+        }
+    }
+
+    // `tall` is the taller child of `a` and `short` the other one. `tall` takes `a`'s place, `a`
+    // keeps `short` and adopts the shorter of `tall`'s children.
+    fn rotate_up(&mut self, a: usize, tall: usize, short: usize) -> usize {
+        let (f, g) = self.nodes[tall].children.expect("taller subtree is not a leaf");
+
+        let a_parent = self.nodes[a].parent;
+        self.nodes[tall].parent = a_parent;
+        self.nodes[a].parent = Some(tall);
+        self.replace_child(a_parent, a, tall);
+
+        let (kept, given) = if self.nodes[f].height > self.nodes[g].height { (f, g) } else { (g, f) };
+        self.nodes[tall].children = Some((a, kept));
+        self.nodes[a].children = Some((short, given));
+        self.nodes[given].parent = Some(a);
+
+        self.refit(a, short, given);
+        self.refit(tall, a, kept);
+        tall
+    }
+}
+
+// Broad phase that keeps one tree proxy per body across steps and only reinserts bodies that
+// left their fat AABB. Pairs of overlapping fat AABBs are kept across steps too, so the tree is
+// only queried for proxies that were reinserted, and bodies at rest cost one AABB test per pair.
+#[derive(Debug, Clone, Default)]
+pub struct TreeBroadPhase {
+    pub tree: DynamicTree,
+    proxies: HashMap<BodyHandle, TrackedProxy>,
+    // Proxies whose fat AABBs overlap, as node indices with the lower one first.
+    fat_pairs: HashSet<(usize, usize)>,
+    step: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TrackedProxy {
+    proxy: ProxyId,
+    // Last AABB center, to tell how far the body moved since.
+    center: Vector2D,
+    // Index into the proxies `pairs` was last given.
+    index: usize,
+    // The `pairs` call that last saw the body.
+    step: u64,
+}
+
+impl TreeBroadPhase {
+    pub fn new(margin: f32) -> Self {
+        TreeBroadPhase {
+            tree: DynamicTree::new(margin),
+            proxies: HashMap::new(),
+            fat_pairs: HashSet::new(),
+            step: 0,
+        }
+    }
+
+    // Returns every pair of proxies whose AABBs overlap, as indices into `proxies` with the lower
+    // index first, sorted.
+    pub fn pairs(&mut self, proxies: &[(BodyHandle, BoundingBox)]) -> Vec<(usize, usize)> {
+        self.step += 1;
+        let step = self.step;
+        let tree = &mut self.tree;
+
+        let mut moved = Vec::new();
+        for (index, (handle, aabb)) in proxies.iter().enumerate() {
+            match self.proxies.get_mut(handle) {
+                Some(tracked) => {
+                    if tree.move_proxy(tracked.proxy, *aabb, aabb.center - tracked.center) {
+                        moved.push(tracked.proxy);
+                    }
+                    tracked.center = aabb.center;
+                    tracked.index = index;
+                    tracked.step = step;
+                }
+                None => {
+                    let proxy = tree.insert(*handle, *aabb);
+                    self.proxies.insert(*handle, TrackedProxy { proxy, center: aabb.center, index, step });
+                    moved.push(proxy);
+                }
+            }
+        }
+
+        let mut removed = HashSet::new();
+        self.proxies.retain(|_, tracked| {
+            let keep = tracked.step == step;
+            if !keep {
+                tree.remove(tracked.proxy);
+                removed.insert(tracked.proxy.0);
+            }
+            keep
+        });
+        if !removed.is_empty() {
+            self.fat_pairs.retain(|(proxy_1, proxy_2)| !removed.contains(proxy_1) && !removed.contains(proxy_2));
+        }
+
+        for proxy in moved {
+            let fat_aabb = tree.fat_aabb(proxy);
+            let fat_pairs = &mut self.fat_pairs;
+            tree.query_aabb(&fat_aabb, |other| {
+                if other != proxy {
+                    fat_pairs.insert((proxy.0.min(other.0), proxy.0.max(other.0)));
+                }
+                true
+            });
+        }
+
+        // Every tracked body was just seen, so its node knows where it sits in `proxies`.
+        let mut index_of = vec![usize::MAX; tree.nodes.len()];
+        for tracked in self.proxies.values() {
+            index_of[tracked.proxy.0] = tracked.index;
+        }
+
+        let mut pairs = Vec::new();
+        self.fat_pairs.retain(|&(proxy_1, proxy_2)| {
+            if !bounding_box_collision(tree.nodes[proxy_1].aabb, tree.nodes[proxy_2].aabb) {
+                return false;
+            }
+            let (index_1, index_2) = (index_of[proxy_1], index_of[proxy_2]);
+            if bounding_box_collision(proxies[index_1].1, proxies[index_2].1) {
+                pairs.push((index_1.min(index_2), index_1.max(index_2)));
+            }
+            true
+        });
+        pairs.sort_unstable();
+        pairs
+    }
+
+    // Bodies whose fat AABB overlaps `aabb`.
+    pub fn query(&self, aabb: &BoundingBox) -> Vec<BodyHandle> {
+        let mut handles = Vec::new();
+        self.tree.query_aabb(aabb, |proxy| {
+            handles.extend(self.tree.handle(proxy));
+            true
+        });
+        handles
+    }
+
+    // Bodies whose fat AABB the segment from `origin` to `end` passes through.
+    pub fn query_ray(&self, origin: Vector2D, end: Vector2D) -> Vec<BodyHandle> {
+        let mut handles = Vec::new();
+        self.tree.raycast(origin, end, |proxy, max_fraction| {
+            handles.extend(self.tree.handle(proxy));
+            max_fraction
+        });
+        handles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::rigid_body::RigidBody;
+    use crate::types::world::World;
+    use crate::utilities::broad_phase::brute_force_pairs;
+
+    // Checks parent links, heights, balance and that every inner node encloses its children.
+    fn validate(tree: &DynamicTree) {
+        let mut leaves = 0;
+        let mut stack: Vec<usize> = tree.root.into_iter().collect();
+        if let Some(root) = tree.root {
+            assert_eq!(tree.nodes[root].parent, None);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &tree.nodes[index];
+            match node.children {
+                Some((child_1, child_2)) => {
+                    for child in [child_1, child_2] {
+                        assert_eq!(tree.nodes[child].parent, Some(index));
+                        // Center and half extents do not round trip exactly through `union`.
+                        assert!(node.aabb.expanded(1e-4).contains(&tree.nodes[child].aabb));
+                        stack.push(child);
+                    }
+                    let (height_1, height_2) = (tree.nodes[child_1].height, tree.nodes[child_2].height);
+                    assert_eq!(node.height, 1 + height_1.max(height_2));
+                    assert!((height_1 - height_2).abs() <= 1, "Unbalanced node {}", index);
+                    assert!(node.handle.is_none());
+                }
+                None => {
+                    assert_eq!(node.height, 0);
+                    assert!(node.handle.is_some());
+                    leaves += 1;
+                }
+            }
+        }
+        assert_eq!(leaves, tree.len());
+    }
+
+    fn handles(count: usize) -> Vec<BodyHandle> {
+        let mut world = World::new();
+        (0..count)
+            .map(|_| world.insert(RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))))
+            .collect()
+    }
+
+    fn unit_box(x: f32, y: f32) -> BoundingBox {
+        BoundingBox::new(Vector2D::new(x, y), 0.5, 0.5)
+    }
+
+    #[test]
+    fn test_insert_keeps_tree_balanced() {
+        let mut tree = DynamicTree::default();
+        // Inserting along a line is the worst case for an unbalanced tree.
+        for (i, handle) in handles(256).into_iter().enumerate() {
+            tree.insert(handle, unit_box(i as f32 * 2.0, 0.0));
+            validate(&tree);
+        }
+
+        assert_eq!(tree.len(), 256);
+        assert!(tree.height() <= 12, "Height {}", tree.height());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = DynamicTree::default();
+        let body_handles = handles(50);
+        let proxies: Vec<ProxyId> = body_handles.iter()
+            .enumerate()
+            .map(|(i, handle)| tree.insert(*handle, unit_box(i as f32, (i % 7) as f32)))
+            .collect();
+
+        for (i, proxy) in proxies.iter().enumerate().filter(|(i, _)| i % 3 == 0) {
+            assert_eq!(tree.remove(*proxy), Some(body_handles[i]));
+            validate(&tree);
+        }
+        assert_eq!(tree.remove(proxies[0]), None);
+        assert_eq!(tree.len(), 33);
+
+        for (i, proxy) in proxies.iter().enumerate().filter(|(i, _)| i % 3 != 0) {
+            tree.remove(*proxy);
+            validate(&tree);
+            assert_eq!(tree.handle(*proxy), None, "Proxy {} still alive", i);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), -1);
+    }
+
+    #[test]
+    fn test_move_proxy_within_margin() {
+        let mut tree = DynamicTree::new(0.5);
+        let handle = handles(1)[0];
+        let proxy = tree.insert(handle, unit_box(0.0, 0.0));
+
+        assert!(!tree.move_proxy(proxy, unit_box(0.2, 0.0), Vector2D::new(0.2, 0.0)));
+        assert!(tree.move_proxy(proxy, unit_box(1.0, 0.0), Vector2D::new(0.8, 0.0)));
+        // Stretched ahead of the motion.
+        assert!(tree.fat_aabb(proxy).contains(&unit_box(2.5, 0.0)));
+        assert!(!tree.move_proxy(proxy, unit_box(2.0, 0.0), Vector2D::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn test_query_aabb() {
+        let mut tree = DynamicTree::new(0.0);
+        let body_handles = handles(100);
+        for (i, handle) in body_handles.iter().enumerate() {
+            tree.insert(*handle, unit_box((i % 10) as f32 * 2.0, (i / 10) as f32 * 2.0));
+        }
+
+        let mut found = Vec::new();
+        tree.query_aabb(&BoundingBox::from_min_max(Vector2D::new(0.5, 1.0), Vector2D::new(5.0, 4.0)), |proxy| {
+            found.push(tree.handle(proxy).unwrap());
+            true
+        });
+        found.sort();
+
+        // Columns 1 and 2 of rows 1 and 2, plus column 0 which just touches the left edge.
+        let mut expected: Vec<BodyHandle> = [10, 11, 12, 20, 21, 22]
+            .iter()
+            .map(|&i| body_handles[i])
+            .collect();
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let mut visited = 0;
+        tree.query_aabb(&BoundingBox::new(Vector2D::new(9.0, 9.0), 20.0, 20.0), |_| {
+            visited += 1;
+            visited < 5
+        });
+        assert_eq!(visited, 5);
+    }
+
+    #[test]
+    fn test_raycast_clips_to_nearest() {
+        let mut tree = DynamicTree::new(0.0);
+        let body_handles = handles(10);
+        for (i, handle) in body_handles.iter().enumerate() {
+            tree.insert(*handle, unit_box(i as f32 * 3.0, 0.0));
+        }
+
+        // Box AABBs stand in for the shapes, so the callback clips to the entry fraction.
+        let origin = Vector2D::new(-5.0, 0.0);
+        let end = Vector2D::new(40.0, 0.0);
+        let mut closest = None;
+        tree.raycast(origin, end, |proxy, max_fraction| {
+            match tree.fat_aabb(proxy).raycast(origin, end - origin, max_fraction) {
+                Some(fraction) => {
+                    closest = tree.handle(proxy);
+                    fraction
+                }
+                None => max_fraction,
+            }
+        });
+        assert_eq!(closest, Some(body_handles[0]));
+
+        let mut hits = 0;
+        tree.raycast(Vector2D::new(0.0, 5.0), Vector2D::new(30.0, 5.0), |_, max_fraction| {
+            hits += 1;
+            max_fraction
+        });
+        assert_eq!(hits, 0);
+    }
+
+    #[test]
+    fn test_broad_phase_matches_brute_force() {
+        let body_handles = handles(120);
+        let mut broad_phase = TreeBroadPhase::new(0.2);
+
+        // A few big static platforms among many small bodies.
+        let mut proxies: Vec<(BodyHandle, BoundingBox)> = body_handles.iter().enumerate().map(|(i, handle)| {
+            let aabb = if i < 4 {
+                BoundingBox::new(Vector2D::new(i as f32 * 30.0, -1.0), 20.0, 1.0)
+            }
+            else {
+                unit_box((i % 20) as f32 * 4.5, (i / 20) as f32 * 1.5)
+            };
+            (*handle, aabb)
+        }).collect();
+
+        for step in 0..30 {
+            for (i, (_, aabb)) in proxies.iter_mut().enumerate().skip(4) {
+                aabb.center.y -= 0.3;
+                aabb.center.x += if i % 2 == 0 { 0.4 } else { -0.4 };
+            }
+            if step == 10 {
+                proxies.truncate(100);
+            }
+
+            assert_eq!(broad_phase.pairs(&proxies), brute_force_pairs(&proxies), "Step {}", step);
+            validate(&broad_phase.tree);
+            assert_eq!(broad_phase.tree.len(), proxies.len());
+        }
+    }
+
+    #[test]
+    fn test_broad_phase_pairs_survive_swapped_and_resting_proxies() {
+        let body_handles = handles(6);
+        let mut broad_phase = TreeBroadPhase::new(0.1);
+        let mut proxies: Vec<(BodyHandle, BoundingBox)> = body_handles[..4].iter().enumerate()
+            .map(|(i, handle)| (*handle, unit_box(i as f32 * 0.9, 0.0)))
+            .collect();
+        assert_eq!(broad_phase.pairs(&proxies), vec![(0, 1), (1, 2), (2, 3)]);
+
+        // Nothing moved, so every pair comes from the kept fat pairs.
+        assert_eq!(broad_phase.pairs(&proxies), vec![(0, 1), (1, 2), (2, 3)]);
+
+        // One body leaves and another takes its place, likely in the node it freed.
+        proxies.remove(1);
+        broad_phase.pairs(&proxies);
+        proxies.push((body_handles[4], unit_box(0.9, 0.0)));
+        proxies.push((body_handles[5], unit_box(10.0, 0.0)));
+        assert_eq!(broad_phase.pairs(&proxies), brute_force_pairs(&proxies));
+
+        // Shuffled order, same bodies.
+        proxies.reverse();
+        assert_eq!(broad_phase.pairs(&proxies), brute_force_pairs(&proxies));
+        validate(&broad_phase.tree);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::types::convex_shape::ConvexShape;
+use crate::types::vector_2d::Vector2D;
+
+const MAX_GJK_ITERATIONS: usize = 32;
+const MAX_EPA_ITERATIONS: usize = 64;
+// Relative progress below which GJK and EPA stop refining.
+const TOLERANCE: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Distance {
+    // Zero when the shapes overlap.
+    pub distance: f32,
+    // Closest points on each shape. Meaningless when the shapes overlap.
+    pub point_1: Vector2D,
+    pub point_2: Vector2D,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Penetration {
+    // Points from the first shape towards the second, like contact normals.
+    pub normal: Vector2D,
+    pub depth: f32,
+    // Deepest point of each shape inside the other.
+    pub point_1: Vector2D,
+    pub point_2: Vector2D,
+}
+
+// A point of the Minkowski difference `shape_1 - shape_2`, with the support points it came from.
+#[derive(Debug, Clone, Copy)]
+struct SimplexVertex {
+    point_1: Vector2D,
+    point_2: Vector2D,
+    point: Vector2D,
+    weight: f32,
+}
+
+fn support(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape, direction: Vector2D) -> SimplexVertex {
+    let point_1 = shape_1.support(direction);
+    let point_2 = shape_2.support(direction * -1.0);
+    SimplexVertex { point_1, point_2, point: point_1 - point_2, weight: 1.0 }
+}
+
+pub fn intersects(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape) -> bool {
+    gjk(shape_1, shape_2).1
+}
+
+pub fn distance(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape) -> Distance {
+    let (simplex, overlapping) = gjk(shape_1, shape_2);
+    let point_1 = weighted(&simplex, |vertex| vertex.point_1);
+    let point_2 = weighted(&simplex, |vertex| vertex.point_2);
+    Distance {
+        distance: if overlapping { 0.0 } else { (point_1 - point_2).length() },
+        point_1,
+        point_2,
+    }
+}
+
+// GJK to find out whether the shapes overlap, then EPA to find how far and in which direction
+// they have to move apart. Returns `None` when they do not overlap.
+pub fn penetration(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape) -> Option<Penetration> {
+    let (simplex, overlapping) = gjk(shape_1, shape_2);
+    if !overlapping {
+        return None;
+    }
+
+    let Some(polytope) = initial_polytope(shape_1, shape_2, simplex) else {
+        // Only touching: zero depth along the line between the shapes.
+        let mut normal = shape_2.center() - shape_1.center();
+        if normal.length() == 0.0 {
+            normal = Vector2D::new(0.0, 1.0);
+        }
+        let normal = normal / normal.length();
+        let point = shape_1.support(normal);
+        return Some(Penetration { normal, depth: 0.0, point_1: point, point_2: point });
+    };
+    Some(epa(shape_1, shape_2, polytope))
+}
+
+// Returns the final simplex, whose weighted vertices give the closest point of the Minkowski
+// difference to the origin, and whether the origin is inside it.
+fn gjk(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape) -> (Vec<SimplexVertex>, bool) {
+    let mut direction = shape_2.center() - shape_1.center();
+    if direction.dot(&direction) == 0.0 {
+        direction = Vector2D::new(1.0, 0.0);
+    }
+    let mut simplex = vec![support(shape_1, shape_2, direction)];
+
+    for _ in 0..MAX_GJK_ITERATIONS {
+        solve_simplex(&mut simplex);
+        if simplex.len() == 3 {
+            return (simplex, true);
+        }
+
+        let closest = weighted(&simplex, |vertex| vertex.point);
+        let squared_distance = closest.dot(&closest);
+        if squared_distance < TOLERANCE * TOLERANCE {
+            return (simplex, true);
+        }
+
+        let vertex = support(shape_1, shape_2, closest * -1.0);
+        // The new point gets no closer to the origin than what we have, so we are done. The
+        // absolute floor matters for long thin simplices, whose closest point carries more
+        // roundoff than a purely relative test allows for.
+        let distance = squared_distance.sqrt();
+        if distance - vertex.point.dot(&closest) / distance <= TOLERANCE * distance.max(1.0) {
+            return (simplex, false);
+        }
+        simplex.push(vertex);
+    }
+    // The last vertex pushed has no weight yet.
+    solve_simplex(&mut simplex);
+    let overlapping = simplex.len() == 3;
+    (simplex, overlapping)
+}
+
+fn weighted(simplex: &[SimplexVertex], point: impl Fn(&SimplexVertex) -> Vector2D) -> Vector2D {
+    simplex.iter().fold(Vector2D::new(0.0, 0.0), |sum, vertex| sum + point(vertex) * vertex.weight)
+}
+
+// Reduces the simplex to the smallest feature that holds the point closest to the origin and
+// sets the barycentric weights of that point. A triangle is only kept when it holds the origin.
+fn solve_simplex(simplex: &mut Vec<SimplexVertex>) {
+    match simplex.len() {
+        1 => simplex[0].weight = 1.0,
+        2 => solve_segment(simplex),
+        _ => solve_triangle(simplex),
+    }
+}
+
+fn solve_segment(simplex: &mut Vec<SimplexVertex>) {
+    let (w_1, w_2) = (simplex[0].point, simplex[1].point);
+    let edge = w_2 - w_1;
+
+    let weight_2 = -w_1.dot(&edge);
+    if weight_2 <= 0.0 {
+        simplex.truncate(1);
+        simplex[0].weight = 1.0;
+        return;
+    }
+    let weight_1 = w_2.dot(&edge);
+    if weight_1 <= 0.0 {
+        simplex.swap_remove(0);
+        simplex[0].weight = 1.0;
+        return;
+    }
+    let total = weight_1 + weight_2;
+    simplex[0].weight = weight_1 / total;
+    simplex[1].weight = weight_2 / total;
+}
+
+// Voronoi regions of a triangle, checked vertex first, then edge, then the interior.
+fn solve_triangle(simplex: &mut Vec<SimplexVertex>) {
+    let (v_1, v_2, v_3) = (simplex[0], simplex[1], simplex[2]);
+    let (w_1, w_2, w_3) = (v_1.point, v_2.point, v_3.point);
+
+    let e_12 = w_2 - w_1;
+    let (d12_1, d12_2) = (w_2.dot(&e_12), -w_1.dot(&e_12));
+    let e_13 = w_3 - w_1;
+    let (d13_1, d13_2) = (w_3.dot(&e_13), -w_1.dot(&e_13));
+    let e_23 = w_3 - w_2;
+    let (d23_1, d23_2) = (w_3.dot(&e_23), -w_2.dot(&e_23));
+
+    let area = e_12.cross(&e_13);
+    let d123_1 = area * w_2.cross(&w_3);
+    let d123_2 = area * w_3.cross(&w_1);
+    let d123_3 = area * w_1.cross(&w_2);
+
+    let keep = |simplex: &mut Vec<SimplexVertex>, vertices: &[(SimplexVertex, f32)]| {
+        let total: f32 = vertices.iter().map(|(_, weight)| weight).sum();
+        simplex.clear();
+        simplex.extend(vertices.iter().map(|(vertex, weight)| SimplexVertex { weight: weight / total, ..*vertex }));
+    };
+
+    if d12_2 <= 0.0 && d13_2 <= 0.0 {
+        keep(simplex, &[(v_1, 1.0)]);
+    }
+    else if d12_1 > 0.0 && d12_2 > 0.0 && d123_3 <= 0.0 {
+        keep(simplex, &[(v_1, d12_1), (v_2, d12_2)]);
+    }
+    else if d13_1 > 0.0 && d13_2 > 0.0 && d123_2 <= 0.0 {
+        keep(simplex, &[(v_1, d13_1), (v_3, d13_2)]);
+    }
+    else if d12_1 <= 0.0 && d23_2 <= 0.0 {
+        keep(simplex, &[(v_2, 1.0)]);
+    }
+    else if d13_1 <= 0.0 && d23_1 <= 0.0 {
+        keep(simplex, &[(v_3, 1.0)]);
+    }
+    else if d23_1 > 0.0 && d23_2 > 0.0 && d123_1 <= 0.0 {
+        keep(simplex, &[(v_2, d23_1), (v_3, d23_2)]);
+    }
+    else {
+        keep(simplex, &[(v_1, d123_1), (v_2, d123_2), (v_3, d123_3)]);
+    }
+}
+
+// GJK can stop on a point or segment when the origin lies on it. EPA needs a triangle around the
+// origin, so grow the simplex outwards. Returns `None` if the shapes only touch.
+fn initial_polytope(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape, mut simplex: Vec<SimplexVertex>)
+    -> Option<Vec<SimplexVertex>> {
+    if simplex.len() == 1 {
+        let mut direction = simplex[0].point * -1.0;
+        if direction.dot(&direction) < TOLERANCE * TOLERANCE {
+            direction = Vector2D::new(1.0, 0.0);
+        }
+        simplex.push(support(shape_1, shape_2, direction));
+    }
+    if simplex.len() == 2 {
+        let edge = simplex[1].point - simplex[0].point;
+        let normal = edge.perpendicular();
+        let mut vertex = support(shape_1, shape_2, normal);
+        if (vertex.point - simplex[0].point).dot(&normal) < TOLERANCE {
+            vertex = support(shape_1, shape_2, normal * -1.0);
+        }
+        simplex.push(vertex);
+    }
+
+    let area = (simplex[1].point - simplex[0].point).cross(&(simplex[2].point - simplex[0].point));
+    if area.abs() < TOLERANCE * TOLERANCE {
+        return None;
+    }
+    // Keep the polytope counter-clockwise so edge normals point outwards.
+    if area < 0.0 {
+        simplex.swap(1, 2);
+    }
+    Some(simplex)
+}
+
+// Expands the polytope towards the boundary of the Minkowski difference until the edge closest to
+// the origin is part of that boundary. Its distance is the depth and its normal the direction.
+fn epa(shape_1: &dyn ConvexShape, shape_2: &dyn ConvexShape, mut polytope: Vec<SimplexVertex>) -> Penetration {
+    let mut closest = (0, Vector2D::new(0.0, 1.0), f32::MAX);
+
+    for _ in 0..MAX_EPA_ITERATIONS {
+        closest = (0, Vector2D::new(0.0, 1.0), f32::MAX);
+        for i in 0..polytope.len() {
+            let (start, end) = (polytope[i].point, polytope[(i + 1) % polytope.len()].point);
+            let edge = end - start;
+            let length = edge.length();
+            if length == 0.0 {
+                continue;
+            }
+            let normal = Vector2D::new(edge.y, -edge.x) / length;
+            let distance = normal.dot(&start);
+            if distance < closest.2 {
+                closest = (i, normal, distance);
+            }
+        }
+
+        let (index, normal, distance) = closest;
+        let vertex = support(shape_1, shape_2, normal);
+        if vertex.point.dot(&normal) - distance <= TOLERANCE * distance.abs().max(1.0) {
+            break;
+        }
+        polytope.insert(index + 1, vertex);
+    }
+
+    // Closest point on the final edge, projected back onto both shapes.
+    let (index, normal, distance) = closest;
+    let (start, end) = (polytope[index], polytope[(index + 1) % polytope.len()]);
+    let edge = end.point - start.point;
+    let t = if edge.dot(&edge) > 0.0 {
+        ((normal * distance - start.point).dot(&edge) / edge.dot(&edge)).clamp(0.0, 1.0)
+    }
+    else {
+        0.0
+    };
+    Penetration {
+        normal,
+        depth: distance.max(0.0),
+        point_1: start.point_1 + (end.point_1 - start.point_1) * t,
+        point_2: start.point_2 + (end.point_2 - start.point_2) * t,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::bounding_box::BoundingBox;
+    use crate::types::circle::Circle;
+    use crate::types::polygon::Polygon;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_distance_between_circles() {
+        let circle_1 = Circle::new(Vector2D::new(0.0, 0.0), 1.0);
+        let circle_2 = Circle::new(Vector2D::new(3.0, 4.0), 1.5);
+
+        let result = distance(&circle_1, &circle_2);
+        assert!(approx_eq(result.distance, 2.5), "Distance {}", result.distance);
+        assert!(approx_eq(result.point_1.x, 0.6));
+        assert!(approx_eq(result.point_1.y, 0.8));
+        assert!(approx_eq(result.point_2.x, 2.1));
+        assert!(approx_eq(result.point_2.y, 2.8));
+    }
+
+    #[test]
+    fn test_distance_box_to_polygon_corner() {
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+        let diamond = Polygon::rectangle(0.5, 0.5).transformed(Vector2D::new(3.0, 0.0), std::f32::consts::FRAC_PI_4);
+
+        let result = distance(&bounding_box, &diamond);
+        assert!(approx_eq(result.distance, 2.0 - f32::sqrt(0.5)));
+        assert!(approx_eq(result.point_1.x, 1.0));
+        assert!(approx_eq(result.point_2.y, 0.0));
+    }
+
+    #[test]
+    fn test_intersects() {
+        let bounding_box = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+
+        assert!(intersects(&bounding_box, &Circle::new(Vector2D::new(1.5, 0.0), 0.6)));
+        assert!(!intersects(&bounding_box, &Circle::new(Vector2D::new(1.5, 1.5), 0.6)));
+        assert!(intersects(&bounding_box, &BoundingBox::new(Vector2D::new(0.1, 0.2), 0.2, 0.2)));
+        assert_eq!(distance(&bounding_box, &bounding_box).distance, 0.0);
+    }
+
+    #[test]
+    fn test_penetration_boxes_matches_overlap() {
+        let box_1 = BoundingBox::new(Vector2D::new(0.0, 0.0), 2.0, 1.0);
+        let box_2 = BoundingBox::new(Vector2D::new(0.5, 1.7), 1.0, 1.0);
+
+        let result = penetration(&box_1, &box_2).unwrap();
+        assert!(approx_eq(result.normal.x, 0.0));
+        assert!(approx_eq(result.normal.y, 1.0));
+        assert!(approx_eq(result.depth, 0.3));
+        assert!(approx_eq(result.point_1.y, 1.0));
+        assert!(approx_eq(result.point_2.y, 0.7));
+    }
+
+    #[test]
+    fn test_penetration_circles() {
+        let circle_1 = Circle::new(Vector2D::new(0.0, 0.0), 1.0);
+        let circle_2 = Circle::new(Vector2D::new(-1.2, -1.6), 1.5);
+
+        let result = penetration(&circle_1, &circle_2).unwrap();
+        // The polytope only approximates a curved boundary, so the normal converges more slowly
+        // than the depth.
+        assert!((result.normal.x + 0.6).abs() < 5e-3);
+        assert!((result.normal.y + 0.8).abs() < 5e-3);
+        assert!(approx_eq(result.depth, 0.5));
+    }
+
+    #[test]
+    fn test_penetration_polygon_and_circle() {
+        let triangle = Polygon::new(&[
+            Vector2D::new(-2.0, 0.0),
+            Vector2D::new(2.0, 0.0),
+            Vector2D::new(0.0, 2.0),
+        ]).unwrap();
+        let circle = Circle::new(Vector2D::new(0.0, -0.4), 0.5);
+
+        let result = penetration(&triangle, &circle).unwrap();
+        assert!(approx_eq(result.normal.y, -1.0));
+        assert!(approx_eq(result.depth, 0.1));
+        assert!(penetration(&triangle, &Circle::new(Vector2D::new(0.0, -0.6), 0.5)).is_none());
+    }
+
+    #[test]
+    fn test_penetration_deep_and_concentric() {
+        let large = BoundingBox::new(Vector2D::new(0.0, 0.0), 3.0, 1.0);
+        let small = BoundingBox::new(Vector2D::new(0.0, 0.0), 0.5, 0.5);
+
+        let result = penetration(&large, &small).unwrap();
+        assert!(approx_eq(result.depth, 1.5));
+        assert!(approx_eq(result.normal.x, 0.0));
+    }
+
+    #[test]
+    fn test_touching_shapes() {
+        let box_1 = BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0);
+        let box_2 = BoundingBox::new(Vector2D::new(2.0, 0.0), 1.0, 1.0);
+
+        let result = penetration(&box_1, &box_2).unwrap();
+        assert!(approx_eq(result.depth, 0.0));
+        assert!(approx_eq(result.normal.x, 1.0));
+    }
+
+    #[test]
+    fn test_distance_to_long_thin_shape() {
+        // Closest point sits in the middle of a long edge of the Minkowski difference.
+        let circle = Circle::new(Vector2D::new(-0.155, 0.2), 0.1);
+        let wall = BoundingBox::new(Vector2D::new(0.0, 0.0), 0.05, 3.0);
+
+        let result = distance(&circle, &wall);
+        assert!((result.distance - 0.005).abs() < 1e-4, "Got {}", result.distance);
+        assert!((result.point_2.x + 0.05).abs() < 1e-4);
+    }
+}
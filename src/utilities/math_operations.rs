@@ -1,16 +1,136 @@
 use crate::types::rigid_body::RigidBody;
 use crate::types::vector_2d::Vector2D;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    ExplicitEuler,
+    #[default]
+    SemiImplicitEuler,
+    VelocityVerlet,
+    Rk4,
+    Leapfrog,
+}
+
 pub fn integrate(rigid_body: &mut RigidBody, dt: f32) {
-    if rigid_body.inv_mass == 0.0 {
-        return;
+    let acceleration = rigid_body.force * rigid_body.inv_mass;
+    integrate_with(Integrator::SemiImplicitEuler, rigid_body, dt, |_, _| acceleration);
+}
+
+// Zero stands for an infinite mass or inertia, so it maps to zero instead of infinity.
+pub fn inverse(value: f32) -> f32 {
+    if value == 0.0 {
+        0.0
+    }
+    else {
+        1.0 / value
     }
+}
 
-    let acceleration = rigid_body.force * rigid_body.inv_mass;
-    rigid_body.velocity = rigid_body.velocity + acceleration * dt;
-    rigid_body.position = rigid_body.position + rigid_body.velocity * dt;
+// `acceleration` is called with (position, velocity) and may be evaluated several times per step
+// at intermediate states by the multi-stage methods.
+pub fn integrate_with<F>(integrator: Integrator, rigid_body: &mut RigidBody, dt: f32, acceleration: F)
+where
+    F: Fn(Vector2D, Vector2D) -> Vector2D,
+{
+    // Bodies without mass stay where they are but still turn at their angular velocity, so
+    // kinematic platforms can spin.
+    if rigid_body.inv_mass != 0.0 {
+        let (position, velocity) = match integrator {
+            Integrator::ExplicitEuler => explicit_euler(rigid_body.position, rigid_body.velocity, dt, &acceleration),
+            Integrator::SemiImplicitEuler => semi_implicit_euler(rigid_body.position, rigid_body.velocity, dt, &acceleration),
+            Integrator::VelocityVerlet => velocity_verlet(rigid_body.position, rigid_body.velocity, dt, &acceleration),
+            Integrator::Rk4 => rk4(rigid_body.position, rigid_body.velocity, dt, &acceleration),
+            Integrator::Leapfrog => leapfrog(rigid_body.position, rigid_body.velocity, dt, &acceleration),
+        };
+        rigid_body.position = position;
+        rigid_body.velocity = velocity;
+    }
+
+    let angular_acceleration = rigid_body.torque * rigid_body.inv_inertia;
+    let (angle, angular_velocity) = integrate_angle(
+        integrator,
+        rigid_body.angle,
+        rigid_body.angular_velocity,
+        angular_acceleration,
+        dt,
+    );
+    rigid_body.angle = angle;
+    rigid_body.angular_velocity = angular_velocity;
 
     rigid_body.force = Vector2D::new(0.0, 0.0);
+    rigid_body.torque = 0.0;
+}
+
+// Torque is constant over the step, so the second order methods all land on the exact
+// constant-acceleration update and only the two Euler variants differ.
+fn integrate_angle(integrator: Integrator, angle: f32, angular_velocity: f32, angular_acceleration: f32, dt: f32) -> (f32, f32) {
+    let new_angular_velocity = angular_velocity + angular_acceleration * dt;
+    let new_angle = match integrator {
+        Integrator::ExplicitEuler => angle + angular_velocity * dt,
+        Integrator::SemiImplicitEuler => angle + new_angular_velocity * dt,
+        Integrator::VelocityVerlet | Integrator::Rk4 | Integrator::Leapfrog => {
+            angle + (angular_velocity + new_angular_velocity) * (0.5 * dt)
+        }
+    };
+    (new_angle, new_angular_velocity)
+}
+
+fn explicit_euler<F>(position: Vector2D, velocity: Vector2D, dt: f32, acceleration: &F) -> (Vector2D, Vector2D)
+where
+    F: Fn(Vector2D, Vector2D) -> Vector2D,
+{
+    let a = acceleration(position, velocity);
+    (position + velocity * dt, velocity + a * dt)
+}
+
+fn semi_implicit_euler<F>(position: Vector2D, velocity: Vector2D, dt: f32, acceleration: &F) -> (Vector2D, Vector2D)
+where
+    F: Fn(Vector2D, Vector2D) -> Vector2D,
+{
+    let new_velocity = velocity + acceleration(position, velocity) * dt;
+    (position + new_velocity * dt, new_velocity)
+}
+
+fn velocity_verlet<F>(position: Vector2D, velocity: Vector2D, dt: f32, acceleration: &F) -> (Vector2D, Vector2D)
+where
+    F: Fn(Vector2D, Vector2D) -> Vector2D,
+{
+    let a_start = acceleration(position, velocity);
+    let new_position = position + velocity * dt + a_start * (0.5 * dt * dt);
+    let a_end = acceleration(new_position, velocity + a_start * dt);
+    (new_position, velocity + (a_start + a_end) * (0.5 * dt))
+}
+
+fn rk4<F>(position: Vector2D, velocity: Vector2D, dt: f32, acceleration: &F) -> (Vector2D, Vector2D)
+where
+    F: Fn(Vector2D, Vector2D) -> Vector2D,
+{
+    let half_dt = 0.5 * dt;
+
+    let k1_x = velocity;
+    let k1_v = acceleration(position, velocity);
+    let k2_x = velocity + k1_v * half_dt;
+    let k2_v = acceleration(position + k1_x * half_dt, k2_x);
+    let k3_x = velocity + k2_v * half_dt;
+    let k3_v = acceleration(position + k2_x * half_dt, k3_x);
+    let k4_x = velocity + k3_v * dt;
+    let k4_v = acceleration(position + k3_x * dt, k4_x);
+
+    let sixth_dt = dt / 6.0;
+    (
+        position + (k1_x + k2_x * 2.0 + k3_x * 2.0 + k4_x) * sixth_dt,
+        velocity + (k1_v + k2_v * 2.0 + k3_v * 2.0 + k4_v) * sixth_dt,
+    )
+}
+
+// Drift-kick-drift form, so no half-step velocity has to be stored on the body.
+fn leapfrog<F>(position: Vector2D, velocity: Vector2D, dt: f32, acceleration: &F) -> (Vector2D, Vector2D)
+where
+    F: Fn(Vector2D, Vector2D) -> Vector2D,
+{
+    let half_position = position + velocity * (0.5 * dt);
+    let new_velocity = velocity + acceleration(half_position, velocity) * dt;
+    (half_position + new_velocity * (0.5 * dt), new_velocity)
 }
 
 
@@ -89,4 +209,173 @@ mod tests {
         assert_eq!(body.force.x, 0.0);
         assert_eq!(body.force.y, 0.0);
     }
+
+    #[test]
+    fn test_integrate_angular() {
+        let mut body = RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_inertia(2.0);
+        body.apply_torque(4.0);
+
+        integrate(&mut body, 0.5);
+
+        assert_eq!(body.angular_velocity, 1.0);
+        assert_eq!(body.angle, 0.5);
+        assert_eq!(body.torque, 0.0);
+    }
+
+    #[test]
+    fn test_integrate_angular_locked_without_inertia() {
+        let mut body = RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        );
+        body.apply_torque(4.0);
+
+        integrate(&mut body, 0.5);
+
+        assert_eq!(body.angular_velocity, 0.0);
+        assert_eq!(body.angle, 0.0);
+    }
+
+    #[test]
+    fn test_integrate_spins_body_without_mass() {
+        let mut body = RigidBody::new(0.0, Vector2D::new(1.0, 1.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0));
+        body.angular_velocity = 2.0;
+
+        integrate(&mut body, 0.5);
+
+        assert_eq!(body.angle, 1.0);
+        assert_eq!(body.angular_velocity, 2.0);
+        assert_eq!(body.position.x, 1.0);
+    }
+
+    #[test]
+    fn test_angular_integration_follows_integrator() {
+        let angles = INTEGRATORS.map(|integrator| {
+            let mut body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+                .with_inertia(1.0);
+            body.apply_torque(2.0);
+            integrate_with(integrator, &mut body, 1.0, |_, _| Vector2D::new(0.0, 0.0));
+            assert_eq!(body.angular_velocity, 2.0);
+            body.angle
+        });
+
+        // Exact answer is 0.5 * alpha * t^2 = 1.
+        assert_eq!(angles, [0.0, 2.0, 1.0, 1.0, 1.0]);
+    }
+
+    const INTEGRATORS: [Integrator; 5] = [
+        Integrator::ExplicitEuler,
+        Integrator::SemiImplicitEuler,
+        Integrator::VelocityVerlet,
+        Integrator::Rk4,
+        Integrator::Leapfrog,
+    ];
+
+    fn projectile_error(integrator: Integrator, dt: f32, duration: f32) -> f32 {
+        let gravity = Vector2D::new(0.0, -9.81);
+        let initial_velocity = Vector2D::new(3.0, 12.0);
+        let mut body = RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            initial_velocity,
+            Vector2D::new(0.0, 0.0)
+        );
+
+        let steps = (duration / dt).round() as usize;
+        for _ in 0..steps {
+            integrate_with(integrator, &mut body, dt, |_, _| gravity);
+        }
+
+        let t = steps as f32 * dt;
+        let expected = initial_velocity * t + gravity * (0.5 * t * t);
+        (body.position - expected).length()
+    }
+
+    #[test]
+    fn test_projectile_exact_for_second_order_methods() {
+        for integrator in [Integrator::VelocityVerlet, Integrator::Rk4, Integrator::Leapfrog] {
+            let error = projectile_error(integrator, 1.0 / 60.0, 2.0);
+            assert!(error < 1e-3, "{:?} drifted {} from the analytic projectile", integrator, error);
+        }
+    }
+
+    #[test]
+    fn test_projectile_euler_error_is_first_order() {
+        for integrator in [Integrator::ExplicitEuler, Integrator::SemiImplicitEuler] {
+            let coarse = projectile_error(integrator, 0.02, 2.0);
+            let fine = projectile_error(integrator, 0.01, 2.0);
+            // Expected error is 0.5 * g * t * dt, so halving dt halves it.
+            assert!(f32::abs(coarse - 0.5 * 9.81 * 2.0 * 0.02) < 0.01, "{:?}: {}", integrator, coarse);
+            assert!(f32::abs(coarse / fine - 2.0) < 0.05, "{:?}: {} vs {}", integrator, coarse, fine);
+        }
+    }
+
+    #[test]
+    fn test_explicit_and_semi_implicit_euler_bracket_the_solution() {
+        let gravity = Vector2D::new(0.0, -9.81);
+        let mut explicit = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0));
+        let mut semi_implicit = explicit.clone();
+
+        for _ in 0..60 {
+            integrate_with(Integrator::ExplicitEuler, &mut explicit, 1.0 / 60.0, |_, _| gravity);
+            integrate_with(Integrator::SemiImplicitEuler, &mut semi_implicit, 1.0 / 60.0, |_, _| gravity);
+        }
+
+        let expected = -0.5 * 9.81;
+        assert!(explicit.position.y > expected);
+        assert!(semi_implicit.position.y < expected);
+    }
+
+    #[test]
+    fn test_rk4_most_accurate_on_spring() {
+        let stiffness = 4.0;
+        let spring = |position: Vector2D, _: Vector2D| position * -stiffness;
+        let dt = 0.05;
+        let steps = 100;
+
+        let errors: Vec<f32> = INTEGRATORS.iter().map(|&integrator| {
+            let mut body = RigidBody::new(1.0, Vector2D::new(1.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0));
+            for _ in 0..steps {
+                integrate_with(integrator, &mut body, dt, spring);
+            }
+            let expected = f32::cos(f32::sqrt(stiffness) * dt * steps as f32);
+            f32::abs(body.position.x - expected)
+        }).collect();
+
+        let rk4_error = errors[3];
+        assert!(rk4_error < 1e-4, "RK4 error {}", rk4_error);
+        for (integrator, error) in INTEGRATORS.iter().zip(&errors) {
+            assert!(rk4_error <= *error, "RK4 ({}) less accurate than {:?} ({})", rk4_error, integrator, error);
+        }
+    }
+
+    #[test]
+    fn test_integrate_with_velocity_dependent_force() {
+        let drag = |_: Vector2D, velocity: Vector2D| velocity * -1.0;
+        let mut body = RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0), Vector2D::new(0.0, 0.0));
+
+        for _ in 0..100 {
+            integrate_with(Integrator::Rk4, &mut body, 0.01, drag);
+        }
+
+        assert!(f32::abs(body.velocity.x - f32::exp(-1.0)) < 1e-5);
+        assert!(f32::abs(body.position.x - (1.0 - f32::exp(-1.0))) < 1e-5);
+    }
+
+    #[test]
+    fn test_integrate_with_static_body() {
+        for integrator in INTEGRATORS {
+            let mut body = RigidBody::new(0.0, Vector2D::new(1.0, 1.0), Vector2D::new(2.0, 2.0), Vector2D::new(0.0, 0.0));
+            integrate_with(integrator, &mut body, 1.0, |_, _| Vector2D::new(0.0, -9.81));
+            assert_eq!(body.position.x, 1.0);
+            assert_eq!(body.velocity.y, 2.0);
+        }
+    }
 }
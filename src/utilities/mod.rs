@@ -2,4 +2,13 @@ pub mod math_operations;
 pub mod world_functions;
 pub mod constants;
 pub mod plotter;
-mod collisions;
\ No newline at end of file
+pub mod collisions;
+pub mod contact_solver;
+pub mod broad_phase;
+pub mod spatial_hash;
+pub mod sweep_and_prune;
+pub mod dynamic_tree;
+pub mod gjk;
+pub mod time_of_impact;
+pub mod raycast;
+pub mod world_queries;
@@ -5,7 +5,7 @@ use std::time::Instant;
 // built-in AI. At this point I am going to leave this alone and just suffer with the fact it is
 // unoptimised, unintelligent, piece of shit.
 
-pub fn plot_trajectories(all_positions: &Vec<Vec<(f32, f32)>>) -> Result<(), Box<dyn std::error::Error>> {
+pub fn plot_trajectories(all_positions: &[Vec<(f32, f32)>]) -> Result<(), Box<dyn std::error::Error>> {
     let start_time = Instant::now();
 
     let frame_count = if let Some(first) = all_positions.first() {
@@ -83,8 +83,8 @@ pub fn plot_trajectories(all_positions: &Vec<Vec<(f32, f32)>>) -> Result<(), Box
         }
 
         chart.configure_series_labels()
-            .background_style(&WHITE.mix(0.8))
-            .border_style(&BLACK)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
             .draw()?;
 
         root.present()?;
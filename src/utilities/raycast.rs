@@ -0,0 +1,205 @@
+use crate::types::capsule::Capsule;
+use crate::types::chain::Chain;
+use crate::types::circle::Circle;
+use crate::types::collider::Collider;
+use crate::types::polygon::Polygon;
+use crate::types::segment::Segment;
+use crate::types::vector_2d::Vector2D;
+
+// Where a ray from `origin` to `origin + delta` first enters a shape: the fraction of `delta`
+// travelled and the outward surface normal there. Rays that start inside a shape do not hit it.
+#[derive(Debug, Clone, Copy)]
+pub struct RaycastHit {
+    pub fraction: f32,
+    pub normal: Vector2D,
+}
+
+impl RaycastHit {
+    fn nearer(self, other: Option<RaycastHit>) -> RaycastHit {
+        match other {
+            Some(other) if other.fraction < self.fraction => other,
+            _ => self,
+        }
+    }
+}
+
+// Shapes must already be in world space.
+pub fn ray_collider(origin: Vector2D, delta: Vector2D, shape: &Collider) -> Option<RaycastHit> {
+    match shape {
+        Collider::Circle(circle) => ray_circle(origin, delta, circle),
+        Collider::Box(bounding_box) => ray_polygon(origin, delta, &Polygon::from(*bounding_box)),
+        Collider::Polygon(polygon) => ray_polygon(origin, delta, polygon),
+        Collider::Capsule(capsule) => ray_capsule(origin, delta, capsule),
+        Collider::Segment(segment) => ray_capsule(origin, delta, &Capsule::new(segment.start, segment.end, 0.0)),
+        Collider::Chain(chain) => ray_chain(origin, delta, chain),
+        Collider::Compound(children) => children.iter()
+            .filter_map(|child| ray_collider(origin, delta, child))
+            .reduce(|hit_1, hit_2| hit_1.nearer(Some(hit_2))),
+    }
+}
+
+pub fn ray_circle(origin: Vector2D, delta: Vector2D, circle: &Circle) -> Option<RaycastHit> {
+    let offset = origin - circle.center;
+    let a = delta.dot(&delta);
+    let b = offset.dot(&delta);
+    let c = offset.dot(&offset) - circle.radius * circle.radius;
+    if c <= 0.0 || b > 0.0 || a == 0.0 {
+        return None;
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let fraction = (-b - discriminant.sqrt()) / a;
+    if fraction > 1.0 {
+        return None;
+    }
+    let normal = (offset + delta * fraction) / circle.radius;
+    Some(RaycastHit { fraction, normal })
+}
+
+// Clips the ray against the half plane of every edge; the last edge to cut its start is the one
+// it enters through.
+pub fn ray_polygon(origin: Vector2D, delta: Vector2D, polygon: &Polygon) -> Option<RaycastHit> {
+    let (mut lower, mut upper) = (0.0, 1.0);
+    let mut entry = None;
+
+    for (vertex, normal) in polygon.vertices().iter().zip(polygon.normals()) {
+        let numerator = normal.dot(&(*vertex - origin));
+        let denominator = normal.dot(&delta);
+        if denominator == 0.0 {
+            if numerator < 0.0 {
+                return None;
+            }
+        }
+        else if denominator < 0.0 && numerator < lower * denominator {
+            lower = numerator / denominator;
+            entry = Some(*normal);
+        }
+        else if denominator > 0.0 && numerator < upper * denominator {
+            upper = numerator / denominator;
+        }
+        if upper < lower {
+            return None;
+        }
+    }
+    entry.map(|normal| RaycastHit { fraction: lower, normal })
+}
+
+// Nearest of the two rounded ends and the two flat sides.
+pub fn ray_capsule(origin: Vector2D, delta: Vector2D, capsule: &Capsule) -> Option<RaycastHit> {
+    if capsule.contains_point(origin) {
+        return None;
+    }
+
+    let mut first = None;
+    for end in [capsule.start, capsule.end] {
+        if let Some(hit) = ray_circle(origin, delta, &Circle::new(end, capsule.radius)) {
+            first = Some(hit.nearer(first));
+        }
+    }
+
+    let axis = capsule.end - capsule.start;
+    let length = axis.length();
+    if length > 0.0 {
+        let direction = axis / length;
+        for normal in [direction.perpendicular(), direction.perpendicular() * -1.0] {
+            let height = (origin - capsule.start).dot(&normal) - capsule.radius;
+            let approach = delta.dot(&normal);
+            if height < 0.0 || approach >= 0.0 {
+                continue;
+            }
+            let fraction = -height / approach;
+            let along = (origin + delta * fraction - capsule.start).dot(&direction);
+            if fraction <= 1.0 && (0.0..=length).contains(&along) {
+                first = Some(RaycastHit { fraction, normal }.nearer(first));
+            }
+        }
+    }
+    first
+}
+
+// Edges can only be hit from their front side.
+pub fn ray_chain(origin: Vector2D, delta: Vector2D, chain: &Chain) -> Option<RaycastHit> {
+    chain.edges_near(&Segment::new(origin, origin + delta).aabb())
+        .into_iter()
+        .filter_map(|index| {
+            let edge = chain.edge(index);
+            let segment = Capsule::new(edge.segment.start, edge.segment.end, 0.0);
+            ray_capsule(origin, delta, &segment).filter(|hit| hit.normal.dot(&edge.normal) > 0.0)
+        })
+        .reduce(|hit_1, hit_2| hit_1.nearer(Some(hit_2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::bounding_box::BoundingBox;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn test_ray_circle() {
+        let circle = Circle::new(Vector2D::new(3.0, 0.0), 1.0);
+
+        let hit = ray_circle(Vector2D::new(0.0, 0.0), Vector2D::new(10.0, 0.0), &circle).unwrap();
+        assert!(approx_eq(hit.fraction, 0.2));
+        assert!(approx_eq(hit.normal.x, -1.0));
+
+        assert!(ray_circle(Vector2D::new(0.0, 0.0), Vector2D::new(1.0, 0.0), &circle).is_none());
+        assert!(ray_circle(Vector2D::new(0.0, 2.0), Vector2D::new(10.0, 0.0), &circle).is_none());
+        assert!(ray_circle(Vector2D::new(3.0, 0.0), Vector2D::new(10.0, 0.0), &circle).is_none());
+    }
+
+    #[test]
+    fn test_ray_polygon() {
+        let square = Polygon::rectangle(1.0, 1.0).translated(Vector2D::new(0.0, 5.0));
+
+        let hit = ray_polygon(Vector2D::new(0.5, 0.0), Vector2D::new(0.0, 8.0), &square).unwrap();
+        assert!(approx_eq(hit.fraction, 0.5));
+        assert!(approx_eq(hit.normal.y, -1.0));
+
+        let diagonal = ray_polygon(Vector2D::new(-3.0, 5.5), Vector2D::new(4.0, 0.0), &square).unwrap();
+        assert!(approx_eq(diagonal.fraction, 0.5));
+        assert!(approx_eq(diagonal.normal.x, -1.0));
+
+        assert!(ray_polygon(Vector2D::new(2.0, 0.0), Vector2D::new(0.0, 8.0), &square).is_none());
+        assert!(ray_polygon(Vector2D::new(0.0, 5.0), Vector2D::new(0.0, 8.0), &square).is_none());
+    }
+
+    #[test]
+    fn test_ray_capsule_sides_and_ends() {
+        let capsule = Capsule::new(Vector2D::new(-1.0, 0.0), Vector2D::new(1.0, 0.0), 0.5);
+
+        let side = ray_capsule(Vector2D::new(0.0, 2.0), Vector2D::new(0.0, -4.0), &capsule).unwrap();
+        assert!(approx_eq(side.fraction, 0.375));
+        assert!(approx_eq(side.normal.y, 1.0));
+
+        let end = ray_capsule(Vector2D::new(4.0, 0.0), Vector2D::new(-4.0, 0.0), &capsule).unwrap();
+        assert!(approx_eq(end.fraction, 0.625));
+        assert!(approx_eq(end.normal.x, 1.0));
+    }
+
+    #[test]
+    fn test_ray_chain_is_one_sided() {
+        let chain = Chain::open(&[Vector2D::new(5.0, 0.0), Vector2D::new(-5.0, 0.0)]).unwrap();
+
+        let down = ray_chain(Vector2D::new(0.0, 2.0), Vector2D::new(0.0, -4.0), &chain).unwrap();
+        assert!(approx_eq(down.fraction, 0.5));
+        assert!(approx_eq(down.normal.y, 1.0));
+        assert!(ray_chain(Vector2D::new(0.0, -2.0), Vector2D::new(0.0, 4.0), &chain).is_none());
+    }
+
+    #[test]
+    fn test_ray_collider_compound_takes_nearest() {
+        let compound = Collider::Compound(vec![
+            Collider::Circle(Circle::new(Vector2D::new(6.0, 0.0), 1.0)),
+            Collider::Box(BoundingBox::new(Vector2D::new(3.0, 0.0), 0.5, 0.5)),
+        ]);
+
+        let hit = ray_collider(Vector2D::new(0.0, 0.0), Vector2D::new(10.0, 0.0), &compound).unwrap();
+        assert!(approx_eq(hit.fraction, 0.25));
+    }
+}
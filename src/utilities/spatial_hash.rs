@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use crate::types::bounding_box::BoundingBox;
+use crate::types::world::BodyHandle;
+use crate::utilities::collisions::bounding_box_collision;
+
+// Most cells a single proxy is added to. Anything bigger is kept aside and tested against every
+// other proxy, which is cheaper than filling thousands of cells for one ground plane.
+pub const MAX_CELLS_PER_PROXY: i64 = 64;
+
+// Uniform grid broad phase. Every proxy is added to each cell its AABB touches, and only proxies
+// sharing a cell are tested against each other. Works best when the cell size is around the size
+// of a typical body; bodies spanning more than `MAX_CELLS_PER_PROXY` cells skip the grid.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    pub cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    // Proxies too big for the grid, with their AABBs.
+    oversized: Vec<(usize, BoundingBox)>,
+    // Body behind each proxy index in `cells`, as of the last call to `pairs`.
+    handles: Vec<BodyHandle>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0 && cell_size.is_finite(), "Cell size must be positive, got {}", cell_size);
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn cell_of(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    // Returns every pair of proxies whose AABBs overlap, as indices into `proxies` with the lower
+    // index first, sorted.
+    pub fn pairs(&mut self, proxies: &[(BodyHandle, BoundingBox)]) -> Vec<(usize, usize)> {
+        // Cells are emptied rather than dropped so their allocations carry over to the next step.
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.oversized.clear();
+
+        for (index, (_, aabb)) in proxies.iter().enumerate() {
+            let (min_x, min_y) = self.cell_of(aabb.min().x, aabb.min().y);
+            let (max_x, max_y) = self.cell_of(aabb.max().x, aabb.max().y);
+            if cell_count(min_x, max_x, min_y, max_y) > MAX_CELLS_PER_PROXY {
+                self.oversized.push((index, *aabb));
+                continue;
+            }
+            for x in min_x..=max_x {
+                for y in min_y..=max_y {
+                    self.cells.entry((x, y)).or_default().push(index);
+                }
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for cell in self.cells.values() {
+            for (i, &index_1) in cell.iter().enumerate() {
+                for &index_2 in &cell[i + 1..] {
+                    if bounding_box_collision(proxies[index_1].1, proxies[index_2].1) {
+                        pairs.push((index_1, index_2));
+                    }
+                }
+            }
+        }
+        for &(index_1, aabb) in &self.oversized {
+            for (index_2, (_, other)) in proxies.iter().enumerate() {
+                if index_2 != index_1 && bounding_box_collision(aabb, *other) {
+                    pairs.push((index_1.min(index_2), index_1.max(index_2)));
+                }
+            }
+        }
+        // Proxies sharing several cells are found once per shared cell, and two oversized ones
+        // find each other twice.
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        // Forget cells nothing touched this step so the map does not grow without bound.
+        self.cells.retain(|_, cell| !cell.is_empty());
+        self.handles.clear();
+        self.handles.extend(proxies.iter().map(|(handle, _)| *handle));
+        pairs
+    }
+
+    // Bodies sharing a cell with `aabb` when `pairs` last ran. Only the cells are checked, so
+    // some of them may not overlap it.
+    pub fn query(&self, aabb: &BoundingBox) -> Vec<BodyHandle> {
+        let (min_x, min_y) = self.cell_of(aabb.min().x, aabb.min().y);
+        let (max_x, max_y) = self.cell_of(aabb.max().x, aabb.max().y);
+
+        let mut proxies = Vec::new();
+        // A huge query would visit more empty cells than there are stored ones.
+        if cell_count(min_x, max_x, min_y, max_y) > self.cells.len() as i64 {
+            for (&(x, y), cell) in &self.cells {
+                if (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y) {
+                    proxies.extend_from_slice(cell);
+                }
+            }
+        }
+        else {
+            for x in min_x..=max_x {
+                for y in min_y..=max_y {
+                    if let Some(cell) = self.cells.get(&(x, y)) {
+                        proxies.extend_from_slice(cell);
+                    }
+                }
+            }
+        }
+        proxies.extend(self.oversized.iter()
+            .filter(|(_, other)| bounding_box_collision(*aabb, *other))
+            .map(|(proxy, _)| *proxy));
+        proxies.sort_unstable();
+        proxies.dedup();
+        proxies.into_iter().map(|proxy| self.handles[proxy]).collect()
+    }
+}
+
+// Counted in i64 because the span of a huge box overflows i32 once `cell_of` saturates, which
+// happens for long rays and far away queries.
+fn cell_count(min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> i64 {
+    (max_x as i64 - min_x as i64 + 1) * (max_y as i64 - min_y as i64 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::vector_2d::Vector2D;
+    use crate::types::world::World;
+    use crate::types::rigid_body::RigidBody;
+    use crate::utilities::broad_phase::brute_force_pairs;
+
+    // Small deterministic generator so the scenes are the same on every run.
+    fn scatter(count: usize, extent: f32, seed: u32) -> Vec<(BodyHandle, BoundingBox)> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1u32 << 24) as f32
+        };
+
+        let mut world = World::new();
+        (0..count).map(|_| {
+            let center = Vector2D::new((next() - 0.5) * extent, (next() - 0.5) * extent);
+            let aabb = BoundingBox::new(center, 0.1 + next() * 2.0, 0.1 + next() * 0.5);
+            let handle = world.insert(RigidBody::new(1.0, center, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0)));
+            (handle, aabb)
+        }).collect()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        for (cell_size, seed) in [(0.5, 1), (1.0, 2), (3.0, 3), (10.0, 4)] {
+            let proxies = scatter(400, 40.0, seed);
+            let mut spatial_hash = SpatialHash::new(cell_size);
+
+            let expected = brute_force_pairs(&proxies);
+            assert!(!expected.is_empty());
+            assert_eq!(spatial_hash.pairs(&proxies), expected, "Cell size {}", cell_size);
+        }
+    }
+
+    #[test]
+    fn test_pairs_across_cell_borders() {
+        let mut world = World::new();
+        let handle = world.insert(RigidBody::new(1.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0)));
+        let proxies = [
+            // Touching exactly on the line between cells 0 and -1.
+            (handle, BoundingBox::from_min_max(Vector2D::new(-1.0, -0.5), Vector2D::new(0.0, 0.5))),
+            (handle, BoundingBox::from_min_max(Vector2D::new(0.0, -0.5), Vector2D::new(1.0, 0.5))),
+            // Spans many cells and overlaps the first box from far away.
+            (handle, BoundingBox::from_min_max(Vector2D::new(-20.0, 0.4), Vector2D::new(-0.9, 0.6))),
+        ];
+        let mut spatial_hash = SpatialHash::new(1.0);
+
+        assert_eq!(spatial_hash.pairs(&proxies), vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn test_reuse_between_steps() {
+        let mut spatial_hash = SpatialHash::new(1.0);
+        let mut proxies = scatter(100, 10.0, 7);
+        spatial_hash.pairs(&proxies);
+
+        for (_, aabb) in &mut proxies {
+            aabb.center = aabb.center + Vector2D::new(3.0, -2.0);
+        }
+
+        assert_eq!(spatial_hash.pairs(&proxies), brute_force_pairs(&proxies));
+    }
+
+    #[test]
+    fn test_oversized_proxies_skip_the_grid() {
+        let mut proxies = scatter(50, 20.0, 9);
+        let ground = BoundingBox::from_min_max(Vector2D::new(-1000.0, -1.0), Vector2D::new(1000.0, 1.0));
+        proxies.push((proxies[0].0, ground));
+        proxies.push((proxies[1].0, ground));
+        let mut spatial_hash = SpatialHash::new(0.5);
+
+        assert_eq!(spatial_hash.pairs(&proxies), brute_force_pairs(&proxies));
+        assert_eq!(spatial_hash.oversized.len(), 2);
+        // Filling the grid would have taken some 8000 cells for the two ground boxes.
+        assert!(spatial_hash.cells.len() < 1000);
+        assert!(spatial_hash.query(&BoundingBox::new(Vector2D::new(900.0, 0.0), 0.1, 0.1)).contains(&proxies[0].0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_cell_size_is_rejected() {
+        SpatialHash::new(0.0);
+    }
+}
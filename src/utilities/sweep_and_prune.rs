@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use crate::types::bounding_box::BoundingBox;
+use crate::types::world::BodyHandle;
+use crate::utilities::collisions::bounding_box_collision;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SweepAxis {
+    X,
+    Y,
+    // Picks whichever axis the AABB centers are spread along most, checked every step. Only
+    // switches once the other axis is clearly ahead, so scenes spread evenly do not flip back and
+    // forth.
+    #[default]
+    GreatestVariance,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Endpoint {
+    handle: BodyHandle,
+    proxy: usize,
+    min: f32,
+    max: f32,
+}
+
+// How much larger the other axis' variance has to be before `GreatestVariance` switches to it.
+const AXIS_SWITCH_RATIO: f32 = 1.5;
+
+// Sort-and-sweep broad phase. Proxies are kept sorted by the low end of their extent on one axis,
+// and only proxies whose extents overlap on that axis get a full AABB test. The order is kept
+// between steps and repaired with insertion sort, which is close to linear when bodies only move
+// a little each step. New proxies are sorted on their own and merged in.
+#[derive(Debug, Clone)]
+pub struct SweepAndPrune {
+    pub axis: SweepAxis,
+    sorted_on_x: bool,
+    endpoints: Vec<Endpoint>,
+}
+
+impl Default for SweepAndPrune {
+    fn default() -> Self {
+        SweepAndPrune::new(SweepAxis::default())
+    }
+}
+
+impl SweepAndPrune {
+    pub fn new(axis: SweepAxis) -> Self {
+        SweepAndPrune {
+            axis,
+            sorted_on_x: true,
+            endpoints: Vec::new(),
+        }
+    }
+
+    // Returns every pair of proxies whose AABBs overlap, as indices into `proxies` with the lower
+    // index first, sorted.
+    pub fn pairs(&mut self, proxies: &[(BodyHandle, BoundingBox)]) -> Vec<(usize, usize)> {
+        let use_x = match self.axis {
+            SweepAxis::X => true,
+            SweepAxis::Y => false,
+            SweepAxis::GreatestVariance => {
+                let (variance_x, variance_y) = center_variance(proxies);
+                if self.endpoints.is_empty() {
+                    variance_x >= variance_y
+                }
+                else if self.sorted_on_x {
+                    variance_y <= variance_x * AXIS_SWITCH_RATIO
+                }
+                else {
+                    variance_x > variance_y * AXIS_SWITCH_RATIO
+                }
+            }
+        };
+        // The order on the old axis says nothing about the new one, so every endpoint is
+        // rebuilt and sorted from scratch.
+        if use_x != self.sorted_on_x {
+            self.sorted_on_x = use_x;
+            self.endpoints.clear();
+        }
+
+        let tracked = self.update_endpoints(proxies);
+        insertion_sort(&mut self.endpoints[..tracked]);
+        if tracked < self.endpoints.len() {
+            self.endpoints[tracked..].sort_unstable_by(|a, b| a.min.total_cmp(&b.min));
+            merge_sorted(&mut self.endpoints, tracked);
+        }
+
+        let mut pairs = Vec::new();
+        for (i, endpoint_1) in self.endpoints.iter().enumerate() {
+            for endpoint_2 in &self.endpoints[i + 1..] {
+                if endpoint_2.min > endpoint_1.max {
+                    break;
+                }
+                let (proxy_1, proxy_2) = (endpoint_1.proxy, endpoint_2.proxy);
+                if bounding_box_collision(proxies[proxy_1].1, proxies[proxy_2].1) {
+                    pairs.push((proxy_1.min(proxy_2), proxy_1.max(proxy_2)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    // Bodies whose extent on the sorted axis overlapped `aabb` when `pairs` last ran.
+    pub fn query(&self, aabb: &BoundingBox) -> Vec<BodyHandle> {
+        let (min, max) = extent(aabb, self.sorted_on_x);
+        self.endpoints.iter()
+            .take_while(|endpoint| endpoint.min <= max)
+            .filter(|endpoint| endpoint.max >= min)
+            .map(|endpoint| endpoint.handle)
+            .collect()
+    }
+
+    // Refreshes the extents of bodies that are still present, drops removed ones and appends new
+    // ones at the end. Returns how many endpoints were already tracked, which come first.
+    fn update_endpoints(&mut self, proxies: &[(BodyHandle, BoundingBox)]) -> usize {
+        let lookup: HashMap<BodyHandle, usize> = proxies.iter()
+            .enumerate()
+            .map(|(index, (handle, _))| (*handle, index))
+            .collect();
+        let mut tracked = vec![false; proxies.len()];
+        let use_x = self.sorted_on_x;
+
+        self.endpoints.retain_mut(|endpoint| {
+            let Some(&proxy) = lookup.get(&endpoint.handle) else {
+                return false;
+            };
+            tracked[proxy] = true;
+            let (min, max) = extent(&proxies[proxy].1, use_x);
+            *endpoint = Endpoint { handle: endpoint.handle, proxy, min, max };
+            true
+        });
+        let tracked_count = self.endpoints.len();
+
+        for (proxy, (handle, aabb)) in proxies.iter().enumerate() {
+            if !tracked[proxy] {
+                let (min, max) = extent(aabb, use_x);
+                self.endpoints.push(Endpoint { handle: *handle, proxy, min, max });
+            }
+        }
+        tracked_count
+    }
+}
+
+fn extent(aabb: &BoundingBox, use_x: bool) -> (f32, f32) {
+    if use_x {
+        (aabb.min().x, aabb.max().x)
+    }
+    else {
+        (aabb.min().y, aabb.max().y)
+    }
+}
+
+fn center_variance(proxies: &[(BodyHandle, BoundingBox)]) -> (f32, f32) {
+    if proxies.is_empty() {
+        return (0.0, 0.0);
+    }
+    let count = proxies.len() as f32;
+    let (sum_x, sum_y, sum_xx, sum_yy) = proxies.iter().fold((0.0, 0.0, 0.0, 0.0), |sums, (_, aabb)| {
+        let center = aabb.center;
+        (sums.0 + center.x, sums.1 + center.y, sums.2 + center.x * center.x, sums.3 + center.y * center.y)
+    });
+    let (mean_x, mean_y) = (sum_x / count, sum_y / count);
+    (sum_xx / count - mean_x * mean_x, sum_yy / count - mean_y * mean_y)
+}
+
+fn insertion_sort(endpoints: &mut [Endpoint]) {
+    for i in 1..endpoints.len() {
+        let mut j = i;
+        while j > 0 && endpoints[j - 1].min > endpoints[j].min {
+            endpoints.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+// Merges the two sorted runs `endpoints[..split]` and `endpoints[split..]`.
+fn merge_sorted(endpoints: &mut Vec<Endpoint>, split: usize) {
+    let appended = endpoints.split_off(split);
+    let tracked = std::mem::take(endpoints);
+    endpoints.reserve(tracked.len() + appended.len());
+
+    let (mut tracked, mut appended) = (tracked.into_iter().peekable(), appended.into_iter().peekable());
+    while let (Some(old), Some(new)) = (tracked.peek(), appended.peek()) {
+        if new.min < old.min {
+            endpoints.extend(appended.next());
+        }
+        else {
+            endpoints.extend(tracked.next());
+        }
+    }
+    endpoints.extend(tracked);
+    endpoints.extend(appended);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::rigid_body::RigidBody;
+    use crate::types::vector_2d::Vector2D;
+    use crate::types::world::World;
+    use crate::utilities::broad_phase::brute_force_pairs;
+
+    // A long track of boxes with a little vertical scatter, the layout this broad phase is for.
+    fn track(world: &mut World, count: usize) -> Vec<(BodyHandle, BoundingBox)> {
+        (0..count).map(|i| {
+            let center = Vector2D::new(i as f32 * 0.7, (i % 3) as f32 * 0.4);
+            let handle = world.insert(RigidBody::new(1.0, center, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0)));
+            (handle, BoundingBox::new(center, 0.5, 0.3))
+        }).collect()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut world = World::new();
+        let proxies = track(&mut world, 200);
+
+        for axis in [SweepAxis::X, SweepAxis::Y, SweepAxis::GreatestVariance] {
+            let mut sweep_and_prune = SweepAndPrune::new(axis);
+            assert_eq!(sweep_and_prune.pairs(&proxies), brute_force_pairs(&proxies), "Axis {:?}", axis);
+        }
+    }
+
+    #[test]
+    fn test_incremental_updates_match_brute_force() {
+        let mut world = World::new();
+        let mut proxies = track(&mut world, 100);
+        let mut sweep_and_prune = SweepAndPrune::default();
+        sweep_and_prune.pairs(&proxies);
+
+        for step in 0..20 {
+            // Every other box drifts backwards so the order really changes between steps.
+            for (i, (_, aabb)) in proxies.iter_mut().enumerate() {
+                let direction = if i % 2 == 0 { 1.0 } else { -1.0 };
+                aabb.center.x += direction * 0.15;
+            }
+            if step == 5 {
+                proxies.remove(10);
+                proxies.remove(40);
+            }
+            if step == 10 {
+                proxies.extend(track(&mut world, 5));
+            }
+
+            assert_eq!(sweep_and_prune.pairs(&proxies), brute_force_pairs(&proxies), "Step {}", step);
+        }
+    }
+
+    #[test]
+    fn test_greatest_variance_axis() {
+        let mut world = World::new();
+        let horizontal = track(&mut world, 20);
+        let vertical: Vec<(BodyHandle, BoundingBox)> = horizontal.iter()
+            .map(|(handle, aabb)| (*handle, BoundingBox::new(
+                Vector2D::new(aabb.center.y, aabb.center.x), aabb.half_height, aabb.half_width
+            )))
+            .collect();
+        let mut sweep_and_prune = SweepAndPrune::default();
+
+        sweep_and_prune.pairs(&horizontal);
+        assert!(sweep_and_prune.sorted_on_x);
+        assert_eq!(sweep_and_prune.pairs(&vertical), brute_force_pairs(&vertical));
+        assert!(!sweep_and_prune.sorted_on_x);
+    }
+
+    #[test]
+    fn test_greatest_variance_axis_does_not_flip_on_small_changes() {
+        let mut world = World::new();
+        let grid: Vec<(BodyHandle, BoundingBox)> = (0..25).map(|i| {
+            let center = Vector2D::new((i % 5) as f32, (i / 5) as f32);
+            let handle = world.insert(RigidBody::new(1.0, center, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0)));
+            (handle, BoundingBox::new(center, 0.4, 0.4))
+        }).collect();
+        let mut sweep_and_prune = SweepAndPrune::default();
+        sweep_and_prune.pairs(&grid);
+        assert!(sweep_and_prune.sorted_on_x);
+
+        // Slightly taller than wide now, but not by enough to rebuild on the other axis.
+        let stretched: Vec<(BodyHandle, BoundingBox)> = grid.iter()
+            .map(|(handle, aabb)| (*handle, BoundingBox::new(
+                Vector2D::new(aabb.center.x, aabb.center.y * 1.1), aabb.half_width, aabb.half_height
+            )))
+            .collect();
+        assert_eq!(sweep_and_prune.pairs(&stretched), brute_force_pairs(&stretched));
+        assert!(sweep_and_prune.sorted_on_x);
+    }
+
+    #[test]
+    fn test_many_new_proxies_are_merged_in_order() {
+        let mut world = World::new();
+        let mut proxies = track(&mut world, 50);
+        let mut sweep_and_prune = SweepAndPrune::new(SweepAxis::X);
+        sweep_and_prune.pairs(&proxies);
+
+        // Added in reverse so insertion sort alone would have to move each one the whole way.
+        let mut late = track(&mut world, 100);
+        late.reverse();
+        for (_, aabb) in &mut late {
+            aabb.center.x += 0.35;
+        }
+        proxies.extend(late);
+
+        assert_eq!(sweep_and_prune.pairs(&proxies), brute_force_pairs(&proxies));
+        assert!(sweep_and_prune.endpoints.windows(2).all(|pair| pair[0].min <= pair[1].min));
+    }
+}
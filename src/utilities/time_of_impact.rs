@@ -0,0 +1,278 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::capsule::Capsule;
+use crate::types::chain::ChainEdge;
+use crate::types::circle::Circle;
+use crate::types::collider::Collider;
+use crate::types::rigid_body::RigidBody;
+use crate::types::vector_2d::Vector2D;
+use crate::utilities::gjk::distance;
+use crate::utilities::raycast::ray_capsule;
+
+const MAX_ADVANCEMENT_ITERATIONS: usize = 32;
+// Gap the shapes are brought to at the time of impact. Aiming for exact contact would make
+// conservative advancement crawl towards it forever.
+pub const TARGET_SEPARATION: f32 = 0.005;
+const SEPARATION_TOLERANCE: f32 = 0.25 * TARGET_SEPARATION;
+
+// Motion of a body over one step, interpolated linearly in both position and angle.
+#[derive(Debug, Clone, Copy)]
+pub struct Sweep {
+    pub start_position: Vector2D,
+    pub end_position: Vector2D,
+    pub start_angle: f32,
+    pub end_angle: f32,
+}
+
+impl Sweep {
+    pub fn new(start_position: Vector2D, end_position: Vector2D, start_angle: f32, end_angle: f32) -> Self {
+        Sweep { start_position, end_position, start_angle, end_angle }
+    }
+
+    pub fn stationary(position: Vector2D, angle: f32) -> Self {
+        Sweep::new(position, position, angle, angle)
+    }
+
+    // From where the body started the current step to where it is now.
+    pub fn of(body: &RigidBody) -> Self {
+        Sweep::new(body.previous_position, body.position, body.previous_angle, body.angle)
+    }
+
+    pub fn position_at(&self, fraction: f32) -> Vector2D {
+        self.start_position + (self.end_position - self.start_position) * fraction
+    }
+
+    pub fn angle_at(&self, fraction: f32) -> f32 {
+        self.start_angle + (self.end_angle - self.start_angle) * fraction
+    }
+
+    pub fn translation(&self) -> Vector2D {
+        self.end_position - self.start_position
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.end_angle - self.start_angle
+    }
+
+    // Covers the collider everywhere along the sweep.
+    pub fn aabb(&self, collider: &Collider) -> BoundingBox {
+        if self.rotation() == 0.0 {
+            return collider.aabb(self.start_position, self.start_angle)
+                .union(&collider.aabb(self.end_position, self.end_angle));
+        }
+        let radius = bounding_radius(collider);
+        BoundingBox::new(self.start_position, radius, radius)
+            .union(&BoundingBox::new(self.end_position, radius, radius))
+    }
+}
+
+// First fraction of the step at which two swept colliders come within `TARGET_SEPARATION` of
+// each other. Shapes that already touch at the start return `None`, because the contact solver
+// is handling them. A circle against a circle, capsule or segment that does not turn is solved
+// exactly; everything else uses conservative advancement.
+pub fn time_of_impact(collider_1: &Collider, sweep_1: &Sweep, collider_2: &Collider, sweep_2: &Sweep) -> Option<f32> {
+    match (swept_circle_pair(collider_1, sweep_1, collider_2, sweep_2), swept_circle_pair(collider_2, sweep_2, collider_1, sweep_1)) {
+        (Some(fraction), _) | (_, Some(fraction)) => fraction,
+        _ => conservative_advancement(collider_1, sweep_1, collider_2, sweep_2),
+    }
+}
+
+// Returns `None` when the pair has no exact solution.
+fn swept_circle_pair(collider_1: &Collider, sweep_1: &Sweep, collider_2: &Collider, sweep_2: &Sweep) -> Option<Option<f32>> {
+    let Collider::Circle(circle) = collider_1 else {
+        return None;
+    };
+    if (circle.center.length() != 0.0 && sweep_1.rotation() != 0.0) || sweep_2.rotation() != 0.0 {
+        return None;
+    }
+    let target = match collider_2.world_shape(sweep_2.start_position, sweep_2.start_angle) {
+        Collider::Circle(other) => Capsule::new(other.center, other.center, other.radius),
+        Collider::Capsule(capsule) => capsule,
+        Collider::Segment(segment) => Capsule::new(segment.start, segment.end, 0.0),
+        _ => return None,
+    };
+
+    // Work in the frame of the second shape, where only the circle moves.
+    let start = Circle::new(sweep_1.start_position + circle.center.rotated(sweep_1.start_angle), circle.radius);
+    let end = sweep_1.end_position + circle.center.rotated(sweep_1.end_angle);
+    let displacement = end - start.center - sweep_2.translation();
+    let padded = Capsule::new(target.start, target.end, target.radius + TARGET_SEPARATION);
+
+    if padded.inflated(start.radius).contains_point(start.center) {
+        return Some(None);
+    }
+    Some(swept_circle(start, displacement, &padded))
+}
+
+// Fraction of `displacement` at which a circle moving in a straight line first touches the
+// capsule. Zero when it starts out overlapping.
+pub fn swept_circle(circle: Circle, displacement: Vector2D, capsule: &Capsule) -> Option<f32> {
+    let inflated = capsule.inflated(circle.radius);
+    if inflated.contains_point(circle.center) {
+        return Some(0.0);
+    }
+    ray_capsule(circle.center, displacement, &inflated).map(|hit| hit.fraction)
+}
+
+// Steps forward by the largest amount that cannot close the current gap: no point of either
+// shape moves further than its translation plus its rotation times its bounding radius.
+fn conservative_advancement(collider_1: &Collider, sweep_1: &Sweep, collider_2: &Collider, sweep_2: &Sweep) -> Option<f32> {
+    let max_motion = (sweep_1.translation() - sweep_2.translation()).length()
+        + sweep_1.rotation().abs() * bounding_radius(collider_1)
+        + sweep_2.rotation().abs() * bounding_radius(collider_2);
+    if max_motion == 0.0 {
+        return None;
+    }
+
+    let parts_at = |fraction: f32| (
+        convex_parts(&collider_1.world_shape(sweep_1.position_at(fraction), sweep_1.angle_at(fraction))),
+        convex_parts(&collider_2.world_shape(sweep_2.position_at(fraction), sweep_2.angle_at(fraction))),
+    );
+
+    // Chain edges are one-sided, so only the ones the other shape starts out in front of count.
+    let (parts_1, parts_2) = parts_at(0.0);
+    let facing: Vec<(usize, usize)> = (0..parts_1.len())
+        .flat_map(|i| (0..parts_2.len()).map(move |j| (i, j)))
+        .filter(|&(i, j)| faces(&parts_1[i], &parts_2[j]) && faces(&parts_2[j], &parts_1[i]))
+        .collect();
+    let separation = |parts_1: &[(Collider, Option<ChainEdge>)], parts_2: &[(Collider, Option<ChainEdge>)]| {
+        facing.iter()
+            .filter_map(|&(i, j)| Some(distance(parts_1[i].0.as_convex()?, parts_2[j].0.as_convex()?).distance))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    let mut gap = separation(&parts_1, &parts_2);
+    if gap <= TARGET_SEPARATION {
+        return None;
+    }
+
+    let mut fraction = 0.0;
+    for _ in 0..MAX_ADVANCEMENT_ITERATIONS {
+        fraction += (gap - TARGET_SEPARATION) / max_motion;
+        if fraction >= 1.0 {
+            return None;
+        }
+        let (parts_1, parts_2) = parts_at(fraction);
+        gap = separation(&parts_1, &parts_2);
+        if gap <= TARGET_SEPARATION + SEPARATION_TOLERANCE {
+            break;
+        }
+    }
+    Some(fraction)
+}
+
+// World-space convex pieces of a shape, remembering which chain edge each piece came from.
+fn convex_parts(shape: &Collider) -> Vec<(Collider, Option<ChainEdge>)> {
+    match shape {
+        Collider::Compound(children) => children.iter().flat_map(convex_parts).collect(),
+        Collider::Chain(chain) => chain.edges().map(|edge| (Collider::Segment(edge.segment), Some(edge))).collect(),
+        _ => vec![(shape.clone(), None)],
+    }
+}
+
+fn faces(part: &(Collider, Option<ChainEdge>), other: &(Collider, Option<ChainEdge>)) -> bool {
+    let (Some(edge), Some(convex)) = (part.1, other.0.as_convex()) else {
+        return true;
+    };
+    (convex.center() - edge.segment.start).dot(&edge.normal) >= 0.0
+}
+
+// Distance from the body origin to the furthest point of its collider, at any angle.
+fn bounding_radius(collider: &Collider) -> f32 {
+    let aabb = collider.aabb(Vector2D::new(0.0, 0.0), 0.0);
+    let (min, max) = (aabb.min(), aabb.max());
+    Vector2D::new(min.x.abs().max(max.x.abs()), min.y.abs().max(max.y.abs())).length()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::polygon::Polygon;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn wall() -> Collider {
+        Collider::rectangle(0.05, 2.0)
+    }
+
+    #[test]
+    fn test_swept_circle_hits_capsule_side_and_end() {
+        let capsule = Capsule::new(Vector2D::new(0.0, -1.0), Vector2D::new(0.0, 1.0), 0.5);
+
+        let side = swept_circle(Circle::new(Vector2D::new(-5.0, 0.0), 0.5), Vector2D::new(10.0, 0.0), &capsule);
+        assert!(approx_eq(side.unwrap(), 0.4));
+
+        let end = swept_circle(Circle::new(Vector2D::new(0.0, 5.0), 0.5), Vector2D::new(0.0, -10.0), &capsule);
+        assert!(approx_eq(end.unwrap(), 0.3));
+
+        let miss = swept_circle(Circle::new(Vector2D::new(-5.0, 3.0), 0.5), Vector2D::new(10.0, 0.0), &capsule);
+        assert!(miss.is_none());
+
+        let short = swept_circle(Circle::new(Vector2D::new(-5.0, 0.0), 0.5), Vector2D::new(2.0, 0.0), &capsule);
+        assert!(short.is_none());
+    }
+
+    #[test]
+    fn test_time_of_impact_circle_against_segment() {
+        let ball = Collider::circle(0.1);
+        let segment = Collider::segment(Vector2D::new(0.0, -1.0), Vector2D::new(0.0, 1.0));
+        let sweep = Sweep::new(Vector2D::new(-1.0, 0.0), Vector2D::new(3.0, 0.0), 0.0, 0.0);
+
+        let fraction = time_of_impact(&ball, &sweep, &segment, &Sweep::stationary(Vector2D::new(0.0, 0.0), 0.0)).unwrap();
+        let gap = 1.0 - 0.1 - 4.0 * fraction;
+        assert!(approx_eq(gap, TARGET_SEPARATION));
+    }
+
+    #[test]
+    fn test_conservative_advancement_through_thin_wall() {
+        let bullet = Collider::Polygon(Polygon::rectangle(0.1, 0.05));
+        let sweep = Sweep::new(Vector2D::new(-2.0, 0.0), Vector2D::new(2.0, 0.0), 0.0, 0.0);
+
+        let fraction = time_of_impact(&bullet, &sweep, &wall(), &Sweep::stationary(Vector2D::new(0.0, 0.0), 0.0)).unwrap();
+        let gap = -0.05 - (sweep.position_at(fraction).x + 0.1);
+        assert!((0.0..=TARGET_SEPARATION + SEPARATION_TOLERANCE).contains(&gap), "Stopped with a gap of {}", gap);
+    }
+
+    #[test]
+    fn test_conservative_advancement_with_rotation() {
+        // A long spinning plank that only reaches the wall by turning towards it.
+        let plank = Collider::Polygon(Polygon::rectangle(1.0, 0.05));
+        let sweep = Sweep::new(Vector2D::new(-0.9, 0.0), Vector2D::new(-0.9, 0.0), std::f32::consts::FRAC_PI_2, 0.0);
+
+        let fraction = time_of_impact(&plank, &sweep, &wall(), &Sweep::stationary(Vector2D::new(0.0, 0.0), 0.0)).unwrap();
+        let shape = plank.world_shape(sweep.position_at(fraction), sweep.angle_at(fraction));
+        let wall_shape = wall().world_shape(Vector2D::new(0.0, 0.0), 0.0);
+        let gap = distance(shape.as_convex().unwrap(), wall_shape.as_convex().unwrap()).distance;
+        assert!(fraction > 0.0 && fraction < 1.0);
+        assert!(gap > 0.0 && gap <= TARGET_SEPARATION + SEPARATION_TOLERANCE, "Stopped with a gap of {}", gap);
+    }
+
+    #[test]
+    fn test_time_of_impact_skips_touching_and_receding_shapes() {
+        let ball = Collider::circle(0.5);
+        let ground = Collider::rectangle(5.0, 0.5);
+        let ground_sweep = Sweep::stationary(Vector2D::new(0.0, -0.5), 0.0);
+
+        let rolling = Sweep::new(Vector2D::new(0.0, 0.5), Vector2D::new(1.0, 0.5), 0.0, 0.0);
+        assert!(time_of_impact(&ball, &rolling, &ground, &ground_sweep).is_none());
+
+        let leaving = Sweep::new(Vector2D::new(0.0, 1.0), Vector2D::new(0.0, 3.0), 0.0, 0.0);
+        assert!(time_of_impact(&ball, &leaving, &ground, &ground_sweep).is_none());
+    }
+
+    #[test]
+    fn test_time_of_impact_ignores_back_of_chain() {
+        let chain = Collider::chain(&[Vector2D::new(5.0, 0.0), Vector2D::new(-5.0, 0.0)]).unwrap();
+        let bullet = Collider::Polygon(Polygon::rectangle(0.1, 0.1));
+        let still = Sweep::stationary(Vector2D::new(0.0, 0.0), 0.0);
+
+        let rising = Sweep::new(Vector2D::new(0.0, -2.0), Vector2D::new(0.0, 2.0), 0.0, 0.0);
+        assert!(time_of_impact(&bullet, &rising, &chain, &still).is_none());
+
+        let falling = Sweep::new(Vector2D::new(0.0, 2.0), Vector2D::new(0.0, -2.0), 0.0, 0.0);
+        let fraction = time_of_impact(&bullet, &falling, &chain, &still).unwrap();
+        let gap = falling.position_at(fraction).y - 0.1;
+        assert!(gap > 0.0 && gap <= TARGET_SEPARATION + SEPARATION_TOLERANCE, "Stopped with a gap of {}", gap);
+    }
+}
@@ -1,95 +1,1308 @@
+use std::collections::HashMap;
+
+use crate::types::bounding_box::BoundingBox;
+use crate::types::contact::Contact;
+use crate::types::contact_event::{ContactEvent, ContactEventKind};
+use crate::types::pre_solve_contact::{ContactProperties, PreSolveContact};
 use crate::types::rigid_body::RigidBody;
-use crate::utilities::constants::G;
-use crate::utilities::math_operations::integrate;
+use crate::types::sensor_event::{SensorEvent, SensorEventKind};
+use crate::types::solver_settings::SolverSettings;
+use crate::types::world::{gravity_at, BodyHandle, World};
+use crate::utilities::collisions::{body_contacts, body_overlap, bounding_box_collision};
+use crate::utilities::contact_solver::{impulse_cache, solve_contacts, ContactConstraint};
+use crate::utilities::math_operations::integrate_with;
+use crate::utilities::time_of_impact::{time_of_impact, Sweep, TARGET_SEPARATION};
+
+pub fn physics_step(world: &mut World, dt: f32) {
+    physics_step_with(world, dt, |_| {});
+}
+
+// `pre_solve` sees every contact of the step before it is solved and may disable or change it.
+pub fn physics_step_with(world: &mut World, dt: f32, pre_solve: impl FnMut(&mut PreSolveContact)) {
+    for (_, body) in world.iter_mut() {
+        body.previous_position = body.position;
+        body.previous_angle = body.angle;
+    }
+
+    integrate_bodies(world, dt);
+    advance_bullets(world, dt);
+    let indexed: Vec<(BodyHandle, BoundingBox)> = world.iter()
+        .filter_map(|(handle, body)| body.aabb().map(|aabb| (handle, aabb)))
+        .collect();
+    solve_collisions(world, pre_solve);
+
+    // The broad phase has seen every body where it was before the solver. Bodies the position
+    // solver pushed out of that box are left for queries to check directly.
+    world.all_unindexed = false;
+    world.unindexed = indexed.into_iter()
+        .filter(|(handle, aabb)| world.get(*handle).and_then(|body| body.aabb()).is_some_and(|now| !aabb.contains(&now)))
+        .map(|(handle, _)| handle)
+        .collect();
+}
+
+// Gravity is sampled at every intermediate state the integrator asks for, so bodies crossing
+// into a gravity zone mid-step are handled by the multi-stage methods.
+pub fn integrate_bodies(world: &mut World, dt: f32) {
+    let gravity = world.gravity;
+    let integrator = world.integrator;
+    let zones = std::mem::take(&mut world.gravity_zones);
+
+    for (_, body) in world.iter_mut() {
+        let force_acceleration = body.force * body.inv_mass;
+        let gravity_scale = body.gravity_scale;
+        integrate_with(integrator, body, dt, |position, _| {
+            gravity_at(gravity, &zones, position) * gravity_scale + force_acceleration
+        });
+    }
+
+    world.gravity_zones = zones;
+}
+
+// Most impacts a bullet is taken through in one step. After the last one it stays where it hit.
+const MAX_BULLET_IMPACTS: usize = 8;
+
+// Sweeps every bullet against the other bodies and sub-steps it through whatever it hits. At each
+// time of impact the bullet is moved there, just overlapping the body it hit, which is moved to
+// where it was at that moment. The contact between the two is solved, and both carry on with
+// their new velocities for the rest of the step. Bullets that already touch something at the
+// start of the step leave it to the contact solver.
+pub fn advance_bullets(world: &mut World, dt: f32) {
+    let bullets: Vec<BodyHandle> = world.iter()
+        .filter(|(_, body)| body.bullet && !body.sensor && body.inv_mass > 0.0 && body.collider.is_some())
+        .map(|(handle, _)| handle)
+        .collect();
+    if bullets.is_empty() {
+        return;
+    }
+
+    // Index everything by where it swept through this step, so the broad phase can pick out the
+    // bodies each bullet may hit. `find_contacts` puts the usual boxes back afterwards.
+    let swept: Vec<(BodyHandle, BoundingBox)> = world.iter()
+        .filter_map(|(handle, body)| Some((handle, Sweep::of(body).aabb(body.collider.as_ref()?))))
+        .collect();
+    world.broad_phase.pairs(&swept);
+
+    for handle in bullets {
+        let Some(body) = world.get(handle) else {
+            continue;
+        };
+        let mut sweep = Sweep::of(body);
+        // Fraction of the step that `sweep` starts at.
+        let mut elapsed = 0.0;
+        for impact in 1..=MAX_BULLET_IMPACTS {
+            let Some((other, fraction)) = first_impact(world, handle, &sweep, elapsed) else {
+                break;
+            };
+            let time = elapsed + (1.0 - elapsed) * fraction;
+            let remaining = (1.0 - time) * dt;
+            if !solve_impact(world, handle, &sweep, other, elapsed, fraction, remaining) || impact == MAX_BULLET_IMPACTS {
+                break;
+            }
+            let Some(body) = world.get_mut(handle) else {
+                break;
+            };
+            sweep = Sweep::new(
+                body.position,
+                body.position + body.velocity * remaining,
+                body.angle,
+                body.angle + body.angular_velocity * remaining,
+            );
+            body.position = sweep.end_position;
+            body.angle = sweep.end_angle;
+            elapsed = time;
+        }
+    }
+}
+
+// Where a body moves over the part of the step after `elapsed`.
+fn remaining_sweep(body: &RigidBody, elapsed: f32) -> Sweep {
+    let sweep = Sweep::of(body);
+    Sweep::new(sweep.position_at(elapsed), sweep.end_position, sweep.angle_at(elapsed), sweep.end_angle)
+}
+
+// The body the bullet following `sweep` hits first, and when, as a fraction of `sweep`. Checked
+// in handle order so every broad phase picks the same body on a tie.
+fn first_impact(world: &World, handle: BodyHandle, sweep: &Sweep, elapsed: f32) -> Option<(BodyHandle, f32)> {
+    let collider = world.get(handle)?.collider.as_ref()?;
+    let swept_aabb = sweep.aabb(collider);
+    let mut others = world.broad_phase.query(&swept_aabb).unwrap_or_else(|| world.handles());
+    others.sort_unstable();
+    others.dedup();
+
+    others.into_iter()
+        .filter(|other| *other != handle && world.should_collide(handle, *other))
+        .filter_map(|other_handle| {
+            let other = world.get(other_handle)?;
+            let other_collider = other.collider.as_ref()?;
+            if other.sensor {
+                return None;
+            }
+            let other_sweep = remaining_sweep(other, elapsed);
+            if !bounding_box_collision(swept_aabb, other_sweep.aabb(other_collider)) {
+                return None;
+            }
+            Some((other_handle, time_of_impact(collider, sweep, other_collider, &other_sweep)?))
+        })
+        .min_by(|(_, fraction_1), (_, fraction_2)| fraction_1.total_cmp(fraction_2))
+}
+
+// Moves the bullet and the body it hit to the time of impact and solves the contact between
+// them. A body that can be pushed is then carried on with its new velocity for `remaining`; the
+// bullet is left at the impact for the caller to move on. Returns false when no contact was
+// found there, in which case the bullet just stays at the impact.
+fn solve_impact(world: &mut World, handle: BodyHandle, sweep: &Sweep, other: BodyHandle, elapsed: f32,
+                fraction: f32, remaining: f32) -> bool {
+    let settings = world.solver_settings;
+    // Time of impact stops just short of touching; go a little past it so there is a contact.
+    let distance = sweep.translation().length();
+    let overshoot = TARGET_SEPARATION + 0.5 * settings.slop;
+    let bullet_fraction = if distance > 0.0 { (fraction + overshoot / distance).min(1.0) } else { fraction };
+
+    let Some((bullet, body)) = world.get_pair_mut(handle, other) else {
+        return false;
+    };
+    bullet.position = sweep.position_at(bullet_fraction);
+    bullet.angle = sweep.angle_at(bullet_fraction);
+    let end_pose = (body.position, body.angle);
+    let other_sweep = remaining_sweep(body, elapsed);
+    body.position = other_sweep.position_at(fraction);
+    body.angle = other_sweep.angle_at(fraction);
+
+    let properties = ContactProperties::mixed(&bullet.material, &body.material);
+    let contacts: Vec<(BodyHandle, BodyHandle, Contact, ContactProperties)> = body_contacts(bullet, body)
+        .into_iter()
+        .map(|contact| (handle, other, contact, properties))
+        .collect();
+    if !contacts.is_empty() {
+        let impact_settings = SolverSettings { warm_starting: false, position_iterations: 0, ..settings };
+        solve_contacts(world, &contacts, &HashMap::new(), &impact_settings);
+    }
+
+    if let Some(body) = world.get_mut(other) {
+        if body.inv_mass > 0.0 && !contacts.is_empty() {
+            body.position = body.position + body.velocity * remaining;
+            body.angle += body.angular_velocity * remaining;
+        }
+        else {
+            (body.position, body.angle) = end_pose;
+        }
+    }
+    !contacts.is_empty()
+}
+
+pub fn solve_collisions(world: &mut World, pre_solve: impl FnMut(&mut PreSolveContact)) {
+    let settings = world.solver_settings;
+    let contacts = find_contacts(world);
+    let contacts = apply_pre_solve(world, contacts, pre_solve);
+    let cache = std::mem::take(&mut world.contact_cache);
+
+    let constraints = solve_contacts(world, &contacts, &cache, &settings);
+    world.contact_cache = impulse_cache(&constraints);
+    if world.record_contact_events {
+        report_contact_events(world, &constraints);
+    }
+    else {
+        // Recording may be turned on later, and must not see these pairs as persisting then.
+        world.touching_pairs.clear();
+    }
+}
+
+// Disabled contacts are dropped before the solver and never show up in contact events.
+fn apply_pre_solve(world: &World, contacts: Vec<(BodyHandle, BodyHandle, Contact)>,
+                   mut pre_solve: impl FnMut(&mut PreSolveContact))
+    -> Vec<(BodyHandle, BodyHandle, Contact, ContactProperties)> {
+    contacts.into_iter()
+        .filter_map(|(handle_1, handle_2, contact)| {
+            let (body_1, body_2) = (world.get(handle_1)?, world.get(handle_2)?);
+            let mut modification = PreSolveContact {
+                handle_1,
+                handle_2,
+                body_1,
+                body_2,
+                contact: &contact,
+                enabled: true,
+                properties: ContactProperties::mixed(&body_1.material, &body_2.material),
+            };
+            pre_solve(&mut modification);
+            let (enabled, properties) = (modification.enabled, modification.properties);
+            enabled.then_some((handle_1, handle_2, contact, properties))
+        })
+        .collect()
+}
+
+// Merges the solved constraints into one event per body pair and queues them, followed by an
+// end event for every pair that touched last step but not this one.
+fn report_contact_events(world: &mut World, constraints: &[ContactConstraint]) {
+    let mut touching: HashMap<(BodyHandle, BodyHandle), ContactEvent> = HashMap::new();
+    for constraint in constraints {
+        let Some(body_1) = world.get(constraint.body_1) else {
+            continue;
+        };
+        let (key, sign) = if constraint.body_1 < constraint.body_2 {
+            ((constraint.body_1, constraint.body_2), 1.0)
+        }
+        else {
+            ((constraint.body_2, constraint.body_1), -1.0)
+        };
+
+        let event = touching.entry(key).or_insert_with(|| ContactEvent {
+            kind: ContactEventKind::Begin,
+            body_1: key.0,
+            body_2: key.1,
+            points: Vec::new(),
+            normal: constraint.normal * sign,
+            normal_impulse: 0.0,
+            tangent_impulse: 0.0,
+        });
+        event.points.extend(constraint.world_points(body_1));
+        event.normal_impulse += constraint.normal_impulse();
+        // The tangent turns with the normal, so flipping the pair flips its impulse too.
+        event.tangent_impulse += constraint.tangent_impulse() * sign;
+    }
 
-pub fn physics_step(bodies: &mut Vec<&mut RigidBody>, dt: f32) {
-    for body in bodies {
-        body.apply_force(G * body.mass);
-        integrate(body, dt);
+    let previous = std::mem::take(&mut world.touching_pairs);
+    let mut events: Vec<ContactEvent> = touching.values()
+        .cloned()
+        .map(|mut event| {
+            if previous.contains_key(&(event.body_1, event.body_2)) {
+                event.kind = ContactEventKind::Persist;
+            }
+            event
+        })
+        .collect();
+    events.sort_by_key(|event| (event.body_1, event.body_2));
+
+    let mut ended: Vec<ContactEvent> = previous.into_iter()
+        .filter(|(key, _)| !touching.contains_key(key))
+        .map(|(_, event)| ContactEvent {
+            kind: ContactEventKind::End,
+            normal_impulse: 0.0,
+            tangent_impulse: 0.0,
+            ..event
+        })
+        .collect();
+    ended.sort_by_key(|event| (event.body_1, event.body_2));
+
+    world.contact_events.extend(events);
+    world.contact_events.extend(ended);
+    world.touching_pairs = touching;
+}
+
+// Only pairs the broad phase reports are handed to the narrow phase. Pairs involving a sensor are
+// only tested for overlap and feed the world's sensor events instead of producing contacts.
+pub fn find_contacts(world: &mut World) -> Vec<(BodyHandle, BodyHandle, Contact)> {
+    let proxies: Vec<(BodyHandle, BoundingBox)> = world.iter()
+        .filter_map(|(handle, body)| body.aabb().map(|aabb| (handle, aabb)))
+        .collect();
+    let pairs = world.broad_phase.pairs(&proxies);
+
+    let mut contacts = Vec::new();
+    let mut overlaps = Vec::new();
+    for (index_1, index_2) in pairs {
+        let (handle_1, handle_2) = (proxies[index_1].0, proxies[index_2].0);
+        let (Some(body_1), Some(body_2)) = (world.get(handle_1), world.get(handle_2)) else {
+            continue;
+        };
+        if !world.should_collide(handle_1, handle_2) {
+            continue;
+        }
+        // Sensors report static bodies too, so they go before the static pair skip.
+        if body_1.sensor || body_2.sensor {
+            if body_overlap(body_1, body_2) {
+                let sensor_first = body_1.sensor && (!body_2.sensor || handle_1 < handle_2);
+                overlaps.push(if sensor_first { (handle_1, handle_2) } else { (handle_2, handle_1) });
+            }
+            continue;
+        }
+        if body_1.inv_mass == 0.0 && body_2.inv_mass == 0.0 {
+            continue;
+        }
+        for contact in body_contacts(body_1, body_2) {
+            contacts.push((handle_1, handle_2, contact));
+        }
+    }
+    if world.record_sensor_events {
+        report_sensor_overlaps(world, overlaps);
     }
+    else {
+        world.sensor_overlaps.clear();
+    }
+    contacts
+}
+
+// Compares this step's sensor overlaps with last step's. Events come out sorted by handle so
+// they do not depend on the broad phase.
+fn report_sensor_overlaps(world: &mut World, mut overlaps: Vec<(BodyHandle, BodyHandle)>) {
+    overlaps.sort();
+    overlaps.dedup();
+    let previous = std::mem::take(&mut world.sensor_overlaps);
+    let event = |kind, (sensor, other)| SensorEvent { kind, sensor, other };
+
+    let mut events: Vec<SensorEvent> = overlaps.iter()
+        .map(|pair| {
+            let kind = if previous.contains(pair) { SensorEventKind::Stay } else { SensorEventKind::Begin };
+            event(kind, *pair)
+        })
+        .collect();
+    let mut ended: Vec<(BodyHandle, BodyHandle)> = previous.into_iter()
+        .filter(|pair| overlaps.binary_search(pair).is_err())
+        .collect();
+    ended.sort();
+    events.extend(ended.into_iter().map(|pair| event(SensorEventKind::End, pair)));
+
+    world.sensor_overlaps = overlaps.into_iter().collect();
+    world.sensor_events.extend(events);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::collider::Collider;
+    use crate::types::collision_filter::CollisionFilter;
+    use crate::types::gravity_zone::GravityZone;
+    use crate::types::material::Material;
+    use crate::types::polygon::Polygon;
     use crate::types::vector_2d::Vector2D;
+    use crate::utilities::broad_phase::BroadPhase;
+    use crate::utilities::math_operations::Integrator;
+    use crate::utilities::sweep_and_prune::SweepAxis;
 
     #[test]
     fn test_physics_step_single_body() {
-        let mut body = RigidBody::new(
+        let mut world = World::new();
+        let handle = world.insert(RigidBody::new(
             2.0, // mass = 2.0
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0)
-        );
+        ));
 
-        physics_step(&mut vec![&mut body], 1.0);
+        physics_step(&mut world, 1.0);
 
+        let body = world.get(handle).unwrap();
         assert_eq!(body.velocity.x, 0.0);
-        assert!(f32::abs(body.velocity.y - 9.81) < 0.001);
+        assert!(f32::abs(body.velocity.y + 9.81) < 0.001);
         assert_eq!(body.position.x, 0.0);
-        assert!(f32::abs(body.position.y - 9.81) < 0.001);
+        assert!(f32::abs(body.position.y + 9.81) < 0.001);
     }
 
     #[test]
     fn test_physics_step_multiple_bodies() {
-        let mut body1 = RigidBody::new(
+        let mut world = World::new();
+        let handle1 = world.insert(RigidBody::new(
             1.0,
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0)
-        );
+        ));
 
-        let mut body2 = RigidBody::new(
+        let handle2 = world.insert(RigidBody::new(
             2.0,
             Vector2D::new(1.0, 1.0),
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0)
-        );
+        ));
 
-        physics_step(&mut vec![&mut body1, &mut body2], 1.0);
+        physics_step(&mut world, 1.0);
 
+        let body1 = world.get(handle1).unwrap();
         assert_eq!(body1.position.x, 0.0);
-        assert!(f32::abs(body1.position.y - 9.81) < 0.001);
+        assert!(f32::abs(body1.position.y + 9.81) < 0.001);
 
+        let body2 = world.get(handle2).unwrap();
         assert_eq!(body2.position.x, 1.0);
-        assert!(f32::abs(body2.position.y - (1.0 + 9.81)) < 0.001);
+        assert!(f32::abs(body2.position.y - (1.0 - 9.81)) < 0.001);
     }
 
     #[test]
     fn test_physics_step_zero_mass_body() {
-        let mut body = RigidBody::new(
+        let mut world = World::new();
+        let handle = world.insert(RigidBody::new(
             0.0,
             Vector2D::new(1.0, 1.0),
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0)
-        );
+        ));
+
+        physics_step(&mut world, 1.0);
 
-        let initial_position = body.position;
-        physics_step(&mut vec![&mut body], 1.0);
-        assert_eq!(body.position.x, initial_position.x);
-        assert_eq!(body.position.y, initial_position.y);
+        let body = world.get(handle).unwrap();
+        assert_eq!(body.position.x, 1.0);
+        assert_eq!(body.position.y, 1.0);
     }
 
     #[test]
     fn test_physics_step_small_timestep() {
-        let mut body = RigidBody::new(
+        let mut world = World::new();
+        let handle = world.insert(RigidBody::new(
             1.0,
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0),
             Vector2D::new(0.0, 0.0)
-        );
+        ));
+
+        physics_step(&mut world, 0.5);
 
-        physics_step(&mut vec![&mut body], 0.5);
+        let body = world.get(handle).unwrap();
         assert_eq!(body.velocity.x, 0.0);
-        assert!(f32::abs(body.velocity.y - 4.905) < 0.001);
+        assert!(f32::abs(body.velocity.y + 4.905) < 0.001);
         assert_eq!(body.position.x, 0.0);
-        assert!(f32::abs(body.position.y - 2.4525) < 0.001);
+        assert!(f32::abs(body.position.y + 2.4525) < 0.001);
+    }
+
+    #[test]
+    fn test_physics_step_empty_world() {
+        let mut world = World::new();
+        physics_step(&mut world, 1.0);
+        assert!(world.is_empty());
+    }
+
+    #[test]
+    fn test_world_step_matches_physics_step() {
+        let body = RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        );
+        let mut world_1 = World::new();
+        let handle_1 = world_1.insert(body.clone());
+        let mut world_2 = World::new();
+        let handle_2 = world_2.insert(body);
+
+        world_1.step(0.1);
+        physics_step(&mut world_2, 0.1);
+
+        assert_eq!(world_1.get(handle_1).unwrap().position.x, world_2.get(handle_2).unwrap().position.x);
+        assert_eq!(world_1.get(handle_1).unwrap().position.y, world_2.get(handle_2).unwrap().position.y);
+    }
+
+    #[test]
+    fn test_physics_step_bodies_without_colliders_pass_through() {
+        let mut world = World::new();
+        let handle_1 = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ));
+        let handle_2 = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ));
+
+        physics_step(&mut world, 0.1);
+
+        assert_eq!(world.get(handle_1).unwrap().position.x, world.get(handle_2).unwrap().position.x);
+    }
+
+    #[test]
+    fn test_physics_step_ball_lands_on_static_ground() {
+        let mut world = World::new();
+        let ground = world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)));
+        let ball = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 2.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::circle(0.5)));
+
+        for _ in 0..300 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+
+        let ball = world.get(ball).unwrap();
+        assert!(ball.position.y > 0.4, "Ball fell through the ground: {:?}", ball.position);
+        assert!(ball.position.y < 0.6);
+        assert_eq!(world.get(ground).unwrap().position.y, -1.0);
+    }
+
+    #[test]
+    fn test_tilted_polygon_crate_settles_flat() {
+        let mut world = World::new();
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)));
+        let crate_handle = world.insert(
+            RigidBody::from_collider(Collider::Polygon(Polygon::rectangle(0.5, 0.5)), 1.0, Vector2D::new(0.0, 1.5))
+                .with_angle(0.3)
+        );
+
+        for _ in 0..300 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+
+        // Landing on a corner tips it onto the nearest face.
+        let body = world.get(crate_handle).unwrap();
+        let quarter_turn = std::f32::consts::FRAC_PI_2;
+        let off_face = body.angle - (body.angle / quarter_turn).round() * quarter_turn;
+        assert!(off_face.abs() < 0.02, "Crate resting at angle {}", body.angle);
+        assert!(f32::abs(body.position.y - 0.5) < 0.03, "Crate resting at {:?}", body.position);
+        assert!(body.angular_velocity.abs() < 0.05);
+    }
+
+    #[test]
+    fn test_capsule_settles_on_segment_ground() {
+        let mut world = World::new();
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::segment(Vector2D::new(-10.0, 0.0), Vector2D::new(10.0, 0.0))));
+        let lying = world.insert(
+            RigidBody::from_collider(Collider::capsule(1.0, 0.5), 1.0, Vector2D::new(-3.0, 1.0))
+                .with_angle(std::f32::consts::FRAC_PI_2 + 0.2)
+        );
+        let standing = world.insert(
+            RigidBody::from_collider(Collider::capsule(0.5, 0.3), 1.0, Vector2D::new(3.0, 1.0))
+                .with_inertia(0.0)
+        );
+
+        for _ in 0..300 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+
+        let body = world.get(lying).unwrap();
+        assert!(f32::abs(body.angle - std::f32::consts::FRAC_PI_2) < 0.02, "Capsule resting at angle {}", body.angle);
+        assert!(f32::abs(body.position.y - 0.5) < 0.03, "Capsule resting at {:?}", body.position);
+        assert!(body.angular_velocity.abs() < 0.05);
+
+        let body = world.get(standing).unwrap();
+        assert!(f32::abs(body.position.y - 0.8) < 0.03, "Character resting at {:?}", body.position);
+        assert!(f32::abs(body.position.x - 3.0) < 1e-3);
+    }
+
+    fn slide_over_ground(ground: Collider, shape: Collider) -> RigidBody {
+        let frictionless = Material::new(1.0, 0.0, 0.0, 0.0);
+        let mut world = World::new();
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(ground).with_material(frictionless));
+        let mut body = RigidBody::from_material(shape, frictionless, Vector2D::new(-15.0, 0.5));
+        body.velocity = Vector2D::new(6.0, 0.0);
+        let handle = world.insert(body);
+
+        for _ in 0..240 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        world.get(handle).unwrap().clone()
+    }
+
+    #[test]
+    fn test_shapes_slide_smoothly_along_chain() {
+        // Forty one-metre edges, running right to left so they face up.
+        let points: Vec<Vector2D> = (0..=40).map(|i| Vector2D::new(20.0 - i as f32, 0.0)).collect();
+
+        for shape in [Collider::circle(0.5), Collider::Polygon(Polygon::rectangle(0.5, 0.5))] {
+            let body = slide_over_ground(Collider::chain(&points).unwrap(), shape);
+            assert!(f32::abs(body.velocity.x - 6.0) < 0.05, "Slowed down to {:?}", body.velocity);
+            assert!(body.velocity.y.abs() < 0.05, "Bounced with {:?}", body.velocity);
+            assert!(f32::abs(body.position.y - 0.5) < 0.03, "Resting at {:?}", body.position);
+            assert!(body.angle.abs() < 0.01, "Tipped over to {}", body.angle);
+        }
+    }
+
+    #[test]
+    fn test_chain_is_solid_from_one_side_only() {
+        let points = [Vector2D::new(5.0, 0.0), Vector2D::new(-5.0, 0.0)];
+        let mut world = World::new();
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::chain(&points).unwrap()));
+        let mut rising = RigidBody::from_material(Collider::circle(0.5), Material::new(1.0, 0.0, 0.5, 0.3), Vector2D::new(0.0, -1.0));
+        rising.velocity = Vector2D::new(0.0, 8.0);
+        let rising = world.insert(rising);
+
+        for _ in 0..180 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+
+        // Jumps up through the edge and lands back on top of it.
+        let body = world.get(rising).unwrap();
+        assert!(f32::abs(body.position.y - 0.5) < 0.03, "Resting at {:?}", body.position);
+    }
+
+    fn fire_at_thin_wall(bullet: bool, shape: Collider, speed: f32) -> RigidBody {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(0.05, 3.0)));
+        let mut projectile = RigidBody::from_material(shape, Material::new(1.0, 0.0, 0.5, 0.3), Vector2D::new(-5.0, 0.2))
+            .with_bullet(bullet);
+        projectile.velocity = Vector2D::new(speed, 0.0);
+        let handle = world.insert(projectile);
+
+        for _ in 0..60 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        world.get(handle).unwrap().clone()
+    }
+
+    #[test]
+    fn test_fast_projectile_tunnels_without_bullet_flag() {
+        let body = fire_at_thin_wall(false, Collider::circle(0.1), 300.0);
+        assert!(body.position.x > 0.05, "Stopped at {:?}", body.position);
+    }
+
+    #[test]
+    fn test_bullets_stop_at_thin_wall() {
+        let shapes = [
+            Collider::circle(0.1),
+            Collider::Polygon(Polygon::rectangle(0.2, 0.05)),
+            Collider::capsule(0.1, 0.05),
+        ];
+        for shape in shapes {
+            let body = fire_at_thin_wall(true, shape, 300.0);
+            assert!(body.position.x < -0.05, "Passed through to {:?}", body.position);
+            assert!(body.velocity.x <= 1e-3, "Still moving into the wall at {:?}", body.velocity);
+        }
+    }
+
+    #[test]
+    fn test_spinning_bullet_stops_at_thin_wall() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(0.05, 3.0)));
+        let mut plank = RigidBody::from_collider(Collider::Polygon(Polygon::rectangle(0.5, 0.05)), 1.0, Vector2D::new(-3.0, 0.0))
+            .with_angular_velocity(40.0)
+            .with_bullet(true);
+        plank.velocity = Vector2D::new(200.0, 0.0);
+        let handle = world.insert(plank);
+
+        for _ in 0..30 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        let body = world.get(handle).unwrap();
+        assert!(body.position.x < -0.05, "Passed through to {:?}", body.position);
+    }
+
+    #[test]
+    fn test_bullets_stop_at_thin_wall_with_every_broad_phase() {
+        for broad_phase in [BroadPhase::spatial_hash(1.0), BroadPhase::sweep_and_prune(SweepAxis::X), BroadPhase::dynamic_tree(0.1)] {
+            let mut world = World::with_gravity(Vector2D::new(0.0, 0.0)).with_broad_phase(broad_phase);
+            world.insert(RigidBody::new(
+                0.0,
+                Vector2D::new(0.0, 0.0),
+                Vector2D::new(0.0, 0.0),
+                Vector2D::new(0.0, 0.0)
+            ).with_collider(Collider::rectangle(0.05, 3.0)));
+            let mut projectile = RigidBody::from_collider(Collider::circle(0.1), 1.0, Vector2D::new(-5.0, 0.2)).with_bullet(true);
+            projectile.velocity = Vector2D::new(300.0, 0.0);
+            let handle = world.insert(projectile);
+
+            for _ in 0..10 {
+                physics_step(&mut world, 1.0 / 60.0);
+            }
+            let body = world.get(handle).unwrap();
+            assert!(body.position.x < -0.05, "{:?}: passed through to {:?}", world.broad_phase, body.position);
+        }
+    }
+
+    #[test]
+    fn test_bouncing_bullet_uses_the_rest_of_the_step() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let bouncy = Material::new(1.0, 1.0, 0.0, 0.0);
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(0.05, 3.0)).with_material(bouncy));
+        let mut projectile = RigidBody::from_material(Collider::circle(0.1), bouncy, Vector2D::new(-2.5, 0.0))
+            .with_bullet(true);
+        projectile.velocity = Vector2D::new(300.0, 0.0);
+        let handle = world.insert(projectile);
+
+        physics_step(&mut world, 1.0 / 60.0);
+
+        // Reaches the wall about halfway through the step and flies back for the other half.
+        let body = world.get(handle).unwrap();
+        assert!(f32::abs(body.velocity.x + 300.0) < 1.0, "Moving at {:?}", body.velocity);
+        assert!(f32::abs(body.position.x + 2.8) < 0.05, "Ended at {:?}", body.position);
+    }
+
+    #[test]
+    fn test_bullet_does_not_end_inside_moving_body() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let mut block = RigidBody::from_collider(Collider::rectangle(0.25, 1.0), 5.0, Vector2D::new(1.0, 0.0));
+        block.velocity = Vector2D::new(-60.0, 0.0);
+        let block = world.insert(block);
+        let mut projectile = RigidBody::from_collider(Collider::circle(0.1), 0.1, Vector2D::new(-2.0, 0.0)).with_bullet(true);
+        projectile.velocity = Vector2D::new(240.0, 0.0);
+        let bullet = world.insert(projectile);
+
+        physics_step(&mut world, 1.0 / 60.0);
+
+        let (block, bullet) = (world.get(block).unwrap(), world.get(bullet).unwrap());
+        let gap = (block.position.x - 0.25) - (bullet.position.x + 0.1);
+        assert!(gap > -2.0 * world.solver_settings.slop, "Bullet {:?} ended inside block {:?}", bullet.position, block.position);
+        assert!(bullet.velocity.x <= block.velocity.x + 1e-3);
+    }
+
+    fn drop_onto_ground(filter: CollisionFilter, ignore: bool) -> RigidBody {
+        let mut world = World::new();
+        let ground = world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)));
+        let handle = world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, 1.0))
+            .with_filter(filter));
+        if ignore {
+            world.ignore_collision(handle, ground);
+        }
+
+        for _ in 0..60 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        world.get(handle).unwrap().clone()
+    }
+
+    #[test]
+    fn test_filtered_bodies_pass_through_each_other() {
+        let landed = drop_onto_ground(CollisionFilter::default(), false);
+        assert!(landed.position.y > 0.4, "Fell to {:?}", landed.position);
+
+        let masked = drop_onto_ground(CollisionFilter::new(0x0002, 0xFFFE), false);
+        assert!(masked.position.y < -1.0, "Stopped at {:?}", masked.position);
+
+        let ignored = drop_onto_ground(CollisionFilter::default(), true);
+        assert!(ignored.position.y < -1.0, "Stopped at {:?}", ignored.position);
+    }
+
+    #[test]
+    fn test_negative_group_never_collides() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let limb = CollisionFilter::default().with_group_index(-1);
+        let handle_1 = world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, 0.0))
+            .with_filter(limb));
+        let handle_2 = world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.5, 0.0))
+            .with_filter(limb));
+        assert!(find_contacts(&mut world).is_empty());
+
+        world.get_mut(handle_2).unwrap().filter = limb.with_group_index(-2);
+        assert!(!find_contacts(&mut world).is_empty());
+        assert!(world.should_collide(handle_1, handle_2));
+    }
+
+    #[test]
+    fn test_bullet_ignores_filtered_wall() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(0.05, 3.0)).with_filter(CollisionFilter::new(0x0002, 0xFFFF)));
+        let mut projectile = RigidBody::from_collider(Collider::circle(0.1), 1.0, Vector2D::new(-5.0, 0.2))
+            .with_bullet(true)
+            .with_filter(CollisionFilter::new(0x0001, 0x0001));
+        projectile.velocity = Vector2D::new(300.0, 0.0);
+        let handle = world.insert(projectile);
+
+        for _ in 0..60 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        assert!(world.get(handle).unwrap().position.x > 0.05);
+    }
+
+    fn static_sensor(collider: Collider, position: Vector2D) -> RigidBody {
+        RigidBody::new(0.0, position, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(collider)
+            .with_sensor(true)
+    }
+
+    #[test]
+    fn test_body_falls_through_sensor_with_begin_stay_end() {
+        let mut world = World::new().with_sensor_events();
+        let zone = world.insert(static_sensor(Collider::rectangle(2.0, 1.0), Vector2D::new(0.0, -2.0)));
+        let ball = world.insert(RigidBody::from_collider(Collider::circle(0.25), 1.0, Vector2D::new(0.0, 0.0)));
+
+        let mut kinds = Vec::new();
+        for _ in 0..90 {
+            physics_step(&mut world, 1.0 / 60.0);
+            for event in world.drain_sensor_events() {
+                assert_eq!((event.sensor, event.other), (zone, ball));
+                kinds.push(event.kind);
+            }
+        }
+
+        assert_eq!(kinds.first(), Some(&SensorEventKind::Begin));
+        assert_eq!(kinds.last(), Some(&SensorEventKind::End));
+        assert!(kinds[1..kinds.len() - 1].iter().all(|kind| *kind == SensorEventKind::Stay));
+        assert!(kinds.len() > 3);
+
+        // Nothing slowed the fall.
+        let body = world.get(ball).unwrap();
+        assert!(f32::abs(body.velocity.y + 9.81 * 1.5) < 0.01, "Falling at {:?}", body.velocity);
+    }
+
+    #[test]
+    fn test_sensors_work_with_any_shape() {
+        let shapes = [
+            Collider::circle(1.0),
+            Collider::rectangle(1.0, 1.0),
+            Collider::Polygon(Polygon::rectangle(1.0, 0.5)),
+            Collider::capsule(0.5, 0.5),
+            Collider::segment(Vector2D::new(-1.0, 0.0), Vector2D::new(1.0, 0.0)),
+            Collider::Compound(vec![Collider::circle(0.5), Collider::rectangle(0.2, 2.0)]),
+        ];
+        for shape in shapes {
+            let mut world = World::with_gravity(Vector2D::new(0.0, 0.0)).with_sensor_events();
+            let sensor = world.insert(static_sensor(shape.clone(), Vector2D::new(0.0, 0.0)));
+            let inside = world.insert(RigidBody::from_collider(Collider::circle(0.2), 1.0, Vector2D::new(0.3, 0.1)));
+            world.insert(RigidBody::from_collider(Collider::circle(0.2), 1.0, Vector2D::new(5.0, 0.0)));
+
+            world.step(1.0 / 60.0);
+            let events: Vec<SensorEvent> = world.drain_sensor_events().collect();
+            assert_eq!(events.len(), 1, "{:?}", shape);
+            assert_eq!((events[0].kind, events[0].sensor, events[0].other), (SensorEventKind::Begin, sensor, inside));
+            assert!(world.get(inside).unwrap().velocity.length() < 1e-6, "{:?}", shape);
+        }
+    }
+
+    #[test]
+    fn test_sensor_reports_end_when_body_removed() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0)).with_sensor_events();
+        let sensor = world.insert(static_sensor(Collider::circle(1.0), Vector2D::new(0.0, 0.0)));
+        let pickup = world.insert(RigidBody::from_collider(Collider::circle(0.2), 1.0, Vector2D::new(0.5, 0.0)));
+        world.step(1.0 / 60.0);
+        world.step(1.0 / 60.0);
+        let kinds: Vec<SensorEventKind> = world.drain_sensor_events().map(|event| event.kind).collect();
+        assert_eq!(kinds, vec![SensorEventKind::Begin, SensorEventKind::Stay]);
+
+        world.remove(pickup);
+        world.step(1.0 / 60.0);
+        let events: Vec<SensorEvent> = world.drain_sensor_events().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, SensorEventKind::End);
+        assert_eq!(events[0].sensor, sensor);
+
+        world.step(1.0 / 60.0);
+        assert!(world.drain_sensor_events().next().is_none());
+    }
+
+    #[test]
+    fn test_static_sensor_reports_static_body() {
+        let mut world = World::new().with_sensor_events();
+        let sensor = world.insert(static_sensor(Collider::rectangle(1.0, 1.0), Vector2D::new(0.0, 0.0)));
+        let wall = world.insert(RigidBody::new(0.0, Vector2D::new(1.5, 0.0), Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0))
+            .with_collider(Collider::rectangle(1.0, 3.0)));
+
+        world.step(1.0 / 60.0);
+
+        let events: Vec<SensorEvent> = world.drain_sensor_events().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].kind, events[0].sensor, events[0].other), (SensorEventKind::Begin, sensor, wall));
+    }
+
+    #[test]
+    fn test_sensor_events_are_not_collected_by_default() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        world.insert(static_sensor(Collider::circle(1.0), Vector2D::new(0.0, 0.0)));
+        let pickup = world.insert(RigidBody::from_collider(Collider::circle(0.2), 1.0, Vector2D::new(0.5, 0.0)));
+        for _ in 0..10 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        assert!(world.drain_sensor_events().next().is_none());
+
+        world.record_sensor_events = true;
+        physics_step(&mut world, 1.0 / 60.0);
+        let events: Vec<SensorEvent> = world.drain_sensor_events().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].kind, events[0].other), (SensorEventKind::Begin, pickup));
+    }
+
+    fn ground(world: &mut World) -> BodyHandle {
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)))
+    }
+
+    fn first_impact(height: f32) -> ContactEvent {
+        let mut world = World::new().with_contact_events();
+        ground(&mut world);
+        world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, height)));
+        loop {
+            physics_step(&mut world, 1.0 / 60.0);
+            if let Some(event) = world.drain_contact_events().next() {
+                return event;
+            }
+        }
+    }
+
+    #[test]
+    fn test_contact_events_begin_persist_end() {
+        let mut world = World::new().with_contact_events();
+        let floor = ground(&mut world);
+        let ball = world.insert(RigidBody::from_material(Collider::circle(0.5), Material::new(1.0, 0.0, 0.5, 0.3), Vector2D::new(0.0, 0.495)));
+
+        for _ in 0..60 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        let events: Vec<ContactEvent> = world.drain_contact_events().collect();
+        assert!(world.drain_contact_events().next().is_none());
+
+        assert_eq!(events[0].kind, ContactEventKind::Begin);
+        assert!(events[1..].iter().all(|event| event.kind == ContactEventKind::Persist));
+        for event in &events {
+            assert_eq!((event.body_1, event.body_2), (floor, ball));
+            assert!(f32::abs(event.normal.y - 1.0) < 1e-3);
+            assert!(!event.points.is_empty());
+            assert!(f32::abs(event.points[0].y) < 0.05, "Touching at {:?}", event.points[0]);
+        }
+
+        // Resting, the floor only has to cancel one step of gravity.
+        let resting = events.last().unwrap();
+        assert!(f32::abs(resting.normal_impulse - 9.81 * world.get(ball).unwrap().mass / 60.0) < 0.05);
+
+        world.remove(ball);
+        physics_step(&mut world, 1.0 / 60.0);
+        let events: Vec<ContactEvent> = world.drain_contact_events().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, ContactEventKind::End);
+        assert_eq!(events[0].normal_impulse, 0.0);
+    }
+
+    #[test]
+    fn test_contact_events_are_not_collected_by_default() {
+        let mut world = World::new();
+        ground(&mut world);
+        world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, 0.495)));
+        for _ in 0..10 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        assert!(world.drain_contact_events().next().is_none());
+
+        world.record_contact_events = true;
+        physics_step(&mut world, 1.0 / 60.0);
+        let events: Vec<ContactEvent> = world.drain_contact_events().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, ContactEventKind::Begin);
+    }
+
+    #[test]
+    fn test_contact_impulse_scales_with_impact_speed() {
+        let low = first_impact(1.0);
+        let high = first_impact(6.0);
+        assert_eq!(low.kind, ContactEventKind::Begin);
+        assert!(high.normal_impulse > 2.0 * low.normal_impulse, "{} vs {}", high.normal_impulse, low.normal_impulse);
+    }
+
+    #[test]
+    fn test_contact_event_tangent_impulse_opposes_sliding() {
+        let mut world = World::new().with_contact_events();
+        ground(&mut world);
+        let mut crate_body = RigidBody::from_collider(Collider::rectangle(0.5, 0.5), 1.0, Vector2D::new(0.0, 0.5));
+        crate_body.velocity = Vector2D::new(4.0, 0.0);
+        world.insert(crate_body);
+
+        physics_step(&mut world, 1.0 / 60.0);
+        let event = world.drain_contact_events().next().unwrap();
+        assert_eq!(event.points.len(), 2);
+        assert!(event.tangent_impulse < 0.0, "Friction impulse {}", event.tangent_impulse);
+    }
+
+    fn jump_at_platform(one_way: bool) -> RigidBody {
+        let mut world = World::new();
+        let platform = ground(&mut world);
+        world.get_mut(platform).unwrap().collider = Some(Collider::rectangle(2.0, 0.1));
+        let mut jumper = RigidBody::from_material(Collider::circle(0.25), Material::new(1.0, 0.0, 0.5, 0.3), Vector2D::new(0.0, -2.0));
+        jumper.velocity = Vector2D::new(0.0, 7.0);
+        let handle = world.insert(jumper);
+
+        // Only solid for bodies coming down onto its top.
+        let pass_from_below = |contact: &mut PreSolveContact| {
+            let (up, other) = if contact.handle_1 == platform {
+                (contact.contact.normal, contact.body_2)
+            }
+            else {
+                (contact.contact.normal * -1.0, contact.body_1)
+            };
+            contact.enabled = !one_way || (up.y > 0.5 && other.velocity.y <= 0.0);
+        };
+
+        for _ in 0..120 {
+            world.step_with_pre_solve(1.0 / 60.0, pass_from_below);
+        }
+        world.get(handle).unwrap().clone()
+    }
+
+    #[test]
+    fn test_pre_solve_one_way_platform() {
+        let landed = jump_at_platform(true);
+        // On top of the platform, which spans y -1.1 to -0.9.
+        assert!(f32::abs(landed.position.y + 0.65) < 0.03, "Resting at {:?}", landed.position);
+
+        let blocked = jump_at_platform(false);
+        assert!(blocked.position.y < -1.3, "Got through to {:?}", blocked.position);
+    }
+
+    #[test]
+    fn test_pre_solve_conveyor_belt() {
+        let mut world = World::new();
+        let belt = ground(&mut world);
+        let parcel = world.insert(RigidBody::from_collider(Collider::rectangle(0.5, 0.5), 1.0, Vector2D::new(0.0, 0.5)));
+
+        for _ in 0..120 {
+            world.step_with_pre_solve(1.0 / 60.0, |contact| {
+                // The belt is the first body, so its surface drags the parcel along +x.
+                if contact.handle_1 == belt {
+                    contact.properties.tangent_speed = 2.0;
+                }
+            });
+        }
+        let body = world.get(parcel).unwrap();
+        assert!(f32::abs(body.velocity.x - 2.0) < 0.05, "Moving at {:?}", body.velocity);
+        assert!(body.position.x > 2.0);
+    }
+
+    #[test]
+    fn test_pre_solve_changes_restitution_and_disables_events() {
+        let bounce = |restitution: f32| {
+            let mut world = World::new();
+            ground(&mut world);
+            let handle = world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, 2.0)));
+            let mut highest_after_bounce = f32::MIN;
+            let mut bounced = false;
+            for _ in 0..90 {
+                world.step_with_pre_solve(1.0 / 60.0, |contact| contact.properties.restitution = restitution);
+                let body = world.get(handle).unwrap();
+                bounced |= body.velocity.y > 0.0;
+                if bounced {
+                    highest_after_bounce = highest_after_bounce.max(body.position.y);
+                }
+            }
+            highest_after_bounce
+        };
+        assert!(bounce(1.0) > 1.7, "Bounced to {}", bounce(1.0));
+        assert!(bounce(0.0) < 0.6, "Bounced to {}", bounce(0.0));
+
+        let mut world = World::new().with_contact_events();
+        ground(&mut world);
+        let handle = world.insert(RigidBody::from_collider(Collider::circle(0.5), 1.0, Vector2D::new(0.0, 1.0)));
+        for _ in 0..60 {
+            world.step_with_pre_solve(1.0 / 60.0, |contact| contact.enabled = false);
+        }
+        assert!(world.get(handle).unwrap().position.y < -1.0);
+        assert!(world.drain_contact_events().next().is_none());
+    }
+
+    fn circle_pile(broad_phase: BroadPhase) -> World {
+        let mut world = World::new().with_broad_phase(broad_phase);
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(10.0, 1.0)));
+        for i in 0..60 {
+            let position = Vector2D::new((i % 10) as f32 * 0.9 - 4.0 + (i / 10) as f32 * 0.1, 0.5 + (i / 10) as f32);
+            world.insert(RigidBody::from_collider(Collider::circle(0.4), 1.0, position));
+        }
+        world
+    }
+
+    #[test]
+    fn test_broad_phases_match_brute_force_simulation() {
+        let mut brute_force = circle_pile(BroadPhase::BruteForce);
+        let mut others = [
+            circle_pile(BroadPhase::spatial_hash(1.0)),
+            circle_pile(BroadPhase::sweep_and_prune(SweepAxis::GreatestVariance)),
+            circle_pile(BroadPhase::dynamic_tree(0.1)),
+        ];
+
+        for _ in 0..120 {
+            physics_step(&mut brute_force, 1.0 / 60.0);
+            for world in &mut others {
+                physics_step(world, 1.0 / 60.0);
+            }
+        }
+
+        for world in &others {
+            for ((_, body_1), (_, body_2)) in brute_force.iter().zip(world.iter()) {
+                assert_eq!(body_1.position.x, body_2.position.x);
+                assert_eq!(body_1.position.y, body_2.position.y);
+            }
+        }
+    }
+
+    fn falling_body() -> RigidBody {
+        RigidBody::new(
+            1.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        )
+    }
+
+    #[test]
+    fn test_physics_step_y_down_gravity() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 9.81));
+        let handle = world.insert(falling_body());
+
+        physics_step(&mut world, 1.0);
+
+        assert!(f32::abs(world.get(handle).unwrap().velocity.y - 9.81) < 0.001);
+    }
+
+    #[test]
+    fn test_physics_step_zero_gravity() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let handle = world.insert(falling_body());
+
+        physics_step(&mut world, 1.0);
+
+        assert_eq!(world.get(handle).unwrap().velocity.y, 0.0);
+        assert_eq!(world.get(handle).unwrap().position.y, 0.0);
+    }
+
+    #[test]
+    fn test_physics_step_gravity_scale() {
+        let mut world = World::new();
+        let balloon = world.insert(falling_body().with_gravity_scale(-0.2));
+        let heavy = world.insert(RigidBody::new(
+            5.0,
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_gravity_scale(2.0));
+
+        physics_step(&mut world, 1.0);
+
+        assert!(f32::abs(world.get(balloon).unwrap().velocity.y - 1.962) < 0.001);
+        assert!(f32::abs(world.get(heavy).unwrap().velocity.y + 19.62) < 0.001);
+    }
+
+    #[test]
+    fn test_physics_step_gravity_zone_overrides_world_gravity() {
+        let mut world = World::new();
+        world.add_gravity_zone(GravityZone::new(
+            BoundingBox::new(Vector2D::new(0.0, 0.0), 1.0, 1.0),
+            Vector2D::new(3.0, 0.0)
+        ));
+        let inside = world.insert(falling_body());
+        let outside = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(5.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ));
+
+        physics_step(&mut world, 0.5);
+
+        let inside = world.get(inside).unwrap();
+        assert!(f32::abs(inside.velocity.x - 1.5) < 0.001);
+        assert_eq!(inside.velocity.y, 0.0);
+        let outside = world.get(outside).unwrap();
+        assert_eq!(outside.velocity.x, 0.0);
+        assert!(f32::abs(outside.velocity.y + 4.905) < 0.001);
+        assert_eq!(world.gravity_zones.len(), 1);
+    }
+
+    #[test]
+    fn test_physics_step_uses_world_integrator() {
+        let mut world = World::new();
+        world.integrator = Integrator::VelocityVerlet;
+        let handle = world.insert(falling_body());
+
+        for _ in 0..60 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+
+        assert!(f32::abs(world.get(handle).unwrap().position.y + 0.5 * 9.81) < 1e-3);
+    }
+
+    #[test]
+    fn test_physics_step_applies_accumulated_force() {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0));
+        let handle = world.insert(falling_body());
+        world.get_mut(handle).unwrap().apply_force(Vector2D::new(2.0, 0.0));
+
+        physics_step(&mut world, 1.0);
+        physics_step(&mut world, 1.0);
+
+        let body = world.get(handle).unwrap();
+        assert_eq!(body.velocity.x, 2.0);
+        assert_eq!(body.force.x, 0.0);
+    }
+
+    #[test]
+    fn test_physics_step_records_previous_position() {
+        let mut world = World::new();
+        let handle = world.insert(RigidBody::new(
+            1.0,
+            Vector2D::new(1.0, 2.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ));
+
+        physics_step(&mut world, 1.0);
+
+        let body = world.get(handle).unwrap();
+        assert_eq!(body.previous_position.x, 1.0);
+        assert_eq!(body.previous_position.y, 2.0);
+        assert_eq!(body.position.x, 2.0);
+    }
+
+    fn slide_distance(material: Material) -> (f32, f32) {
+        let mut world = World::new();
+        world.insert(RigidBody::new(
+            0.0,
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, 0.0)
+        ).with_collider(Collider::rectangle(50.0, 1.0)).with_material(material));
+        let mut crate_body = RigidBody::from_material(Collider::rectangle(0.5, 0.5), material, Vector2D::new(0.0, 0.5));
+        crate_body.velocity = Vector2D::new(5.0, 0.0);
+        let handle = world.insert(crate_body);
+
+        for _ in 0..180 {
+            physics_step(&mut world, 1.0 / 60.0);
+        }
+        let body = world.get(handle).unwrap();
+        (body.position.x, body.velocity.x)
+    }
+
+    #[test]
+    fn test_physics_step_friction_stops_sliding_box() {
+        let (distance, speed) = slide_distance(Material::new(1.0, 0.0, 0.6, 0.4));
+        assert!(speed.abs() < 1e-3, "Box still sliding at {}", speed);
+        assert!(distance > 1.0 && distance < 5.0, "Box slid {}", distance);
     }
 
     #[test]
-    fn test_physics_step_empty_bodies() {
-        let mut empty_vec:  Vec<&mut RigidBody> = vec![];
-        physics_step(&mut empty_vec, 1.0);
+    fn test_physics_step_frictionless_box_keeps_sliding() {
+        let (distance, speed) = slide_distance(Material::new(1.0, 0.0, 0.0, 0.0));
+        assert!(f32::abs(speed - 5.0) < 1e-3);
+        assert!(distance > 14.0);
     }
 }
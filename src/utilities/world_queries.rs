@@ -0,0 +1,316 @@
+use crate::types::bounding_box::BoundingBox;
+use crate::types::collider::Collider;
+use crate::types::contact::Contact;
+use crate::types::rigid_body::RigidBody;
+use crate::types::vector_2d::Vector2D;
+use crate::types::world::{BodyHandle, World};
+use crate::utilities::broad_phase::BroadPhase;
+use crate::utilities::collisions::{bounding_box_collision, shape_contacts};
+use crate::utilities::raycast::ray_collider;
+use crate::utilities::time_of_impact::{time_of_impact, Sweep, TARGET_SEPARATION};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayHit {
+    pub handle: BodyHandle,
+    pub point: Vector2D,
+    // Surface normal of the body that was hit, pointing back towards the ray or cast shape.
+    pub normal: Vector2D,
+    // How far along the ray or cast the hit happened, from 0 at its start to 1 at its end.
+    pub fraction: f32,
+}
+
+// Every query below only looks at bodies with a collider that `filter` accepts, and asks the
+// world's broad phase which bodies are worth checking. The broad phase is as the last step left
+// it, so bodies the position solver pushed out of their indexed AABB, and bodies inserted since
+// or changed through `get_mut`, `get_pair_mut` or `iter_mut`, are checked as well, wherever they
+// are now.
+
+// Nearest body the segment from `origin` to `end` hits. Bodies the segment starts inside of are
+// not hit.
+pub fn raycast(world: &World, origin: Vector2D, end: Vector2D, filter: impl FnMut(BodyHandle, &RigidBody) -> bool)
+    -> Option<RayHit> {
+    raycast_all(world, origin, end, filter).into_iter().next()
+}
+
+// One hit per body, nearest first.
+pub fn raycast_all(world: &World, origin: Vector2D, end: Vector2D, mut filter: impl FnMut(BodyHandle, &RigidBody) -> bool)
+    -> Vec<RayHit> {
+    let found = match world.broad_phase() {
+        // The tree can follow the ray rather than its bounding box.
+        BroadPhase::DynamicTree(tree) => Some(tree.query_ray(origin, end)),
+        broad_phase => {
+            let min = Vector2D::new(origin.x.min(end.x), origin.y.min(end.y));
+            let max = Vector2D::new(origin.x.max(end.x), origin.y.max(end.y));
+            broad_phase.query(&BoundingBox::from_min_max(min, max))
+        }
+    };
+
+    let delta = end - origin;
+    let mut hits: Vec<RayHit> = candidates(world, found, &mut filter)
+        .into_iter()
+        .filter_map(|(handle, body)| {
+            let hit = ray_collider(origin, delta, &body.world_collider()?)?;
+            Some(RayHit { handle, point: origin + delta * hit.fraction, normal: hit.normal, fraction: hit.fraction })
+        })
+        .collect();
+    hits.sort_by(|hit_1, hit_2| hit_1.fraction.total_cmp(&hit_2.fraction));
+    hits
+}
+
+// First body that `shape`, given in world space, runs into when moved by `translation`. A body
+// the shape already overlaps is hit at fraction zero. Meant for circles and boxes, but any
+// collider works.
+pub fn shape_cast(world: &World, shape: &Collider, translation: Vector2D, mut filter: impl FnMut(BodyHandle, &RigidBody) -> bool)
+    -> Option<RayHit> {
+    let sweep = Sweep::new(Vector2D::new(0.0, 0.0), translation, 0.0, 0.0);
+    let found = world.broad_phase().query(&sweep.aabb(shape));
+    let distance = translation.length();
+
+    candidates(world, found, &mut filter)
+        .into_iter()
+        .filter_map(|(handle, body)| {
+            let collider = body.collider.as_ref()?;
+            let body_shape = collider.world_shape(body.position, body.angle);
+            let fraction = if shape_contacts(shape, &body_shape).is_empty() {
+                time_of_impact(shape, &sweep, collider, &Sweep::stationary(body.position, body.angle))?
+            }
+            else {
+                0.0
+            };
+
+            // Time of impact stops just short of touching; go a little past it to find where.
+            let overlap = if distance > 0.0 { 2.0 * TARGET_SEPARATION / distance } else { 0.0 };
+            let touching = shape.translated(translation * (fraction + overlap).min(1.0));
+            let hit = match deepest(shape_contacts(&touching, &body_shape)) {
+                Some((normal, point)) => RayHit { handle, point, normal: normal * -1.0, fraction },
+                None => RayHit {
+                    handle,
+                    point: touching.world_aabb().center,
+                    normal: translation * (-1.0 / distance.max(f32::EPSILON)),
+                    fraction,
+                },
+            };
+            Some(hit)
+        })
+        .min_by(|hit_1, hit_2| hit_1.fraction.total_cmp(&hit_2.fraction))
+}
+
+// Bodies whose collider contains `point`.
+pub fn query_point(world: &World, point: Vector2D, mut filter: impl FnMut(BodyHandle, &RigidBody) -> bool)
+    -> Vec<BodyHandle> {
+    let found = world.broad_phase().query(&BoundingBox::new(point, 0.0, 0.0));
+    candidates(world, found, &mut filter)
+        .into_iter()
+        .filter(|(_, body)| body.world_collider().is_some_and(|shape| shape.contains_point(point)))
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+// Bodies whose AABB overlaps `aabb`.
+pub fn query_aabb(world: &World, aabb: &BoundingBox, mut filter: impl FnMut(BodyHandle, &RigidBody) -> bool)
+    -> Vec<BodyHandle> {
+    let found = world.broad_phase().query(aabb);
+    candidates(world, found, &mut filter)
+        .into_iter()
+        .filter(|(_, body)| body.aabb().is_some_and(|body_aabb| bounding_box_collision(body_aabb, *aabb)))
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+// Turns what the broad phase found into bodies to check, in handle order so every broad phase
+// gives the same answer.
+fn candidates<'a>(world: &'a World, found: Option<Vec<BodyHandle>>, filter: &mut impl FnMut(BodyHandle, &RigidBody) -> bool)
+    -> Vec<(BodyHandle, &'a RigidBody)> {
+    let handles = match found {
+        _ if world.all_unindexed => world.handles(),
+        Some(mut handles) => {
+            handles.extend(world.unindexed.iter().copied());
+            handles.sort_unstable();
+            handles.dedup();
+            handles
+        }
+        None => world.handles(),
+    };
+    handles.into_iter()
+        .filter_map(|handle| world.get(handle).map(|body| (handle, body)))
+        .filter(|(handle, body)| body.collider.is_some() && filter(*handle, body))
+        .collect()
+}
+
+fn deepest(contacts: Vec<Contact>) -> Option<(Vector2D, Vector2D)> {
+    contacts.into_iter()
+        .max_by(|contact_1, contact_2| contact_1.depth.total_cmp(&contact_2.depth))
+        .map(|contact| (contact.normal, contact.points()[0].position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::polygon::Polygon;
+    use crate::utilities::sweep_and_prune::SweepAxis;
+    use crate::utilities::world_functions::physics_step;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    fn static_body(collider: Collider, position: Vector2D) -> RigidBody {
+        RigidBody::new(0.0, position, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.0)).with_collider(collider)
+    }
+
+    // A row of shapes along the x axis, stepped once so the broad phase has seen them.
+    fn row(broad_phase: BroadPhase) -> (World, Vec<BodyHandle>) {
+        let mut world = World::with_gravity(Vector2D::new(0.0, 0.0)).with_broad_phase(broad_phase);
+        let handles = vec![
+            world.insert(static_body(Collider::circle(0.5), Vector2D::new(2.0, 0.0))),
+            world.insert(static_body(Collider::rectangle(0.5, 0.5), Vector2D::new(4.0, 0.0))),
+            world.insert(static_body(Collider::Polygon(Polygon::rectangle(0.5, 1.0)), Vector2D::new(6.0, 0.0))),
+            world.insert(static_body(Collider::capsule(0.5, 0.25), Vector2D::new(8.0, 0.0))),
+        ];
+        physics_step(&mut world, 1.0 / 60.0);
+        (world, handles)
+    }
+
+    fn broad_phases() -> Vec<BroadPhase> {
+        vec![
+            BroadPhase::BruteForce,
+            BroadPhase::spatial_hash(1.0),
+            BroadPhase::sweep_and_prune(SweepAxis::GreatestVariance),
+            BroadPhase::dynamic_tree(0.1),
+        ]
+    }
+
+    #[test]
+    fn test_raycast_first_and_all_hits() {
+        for broad_phase in broad_phases() {
+            let (world, handles) = row(broad_phase);
+            let (origin, end) = (Vector2D::new(0.0, 0.0), Vector2D::new(10.0, 0.0));
+
+            let hit = raycast(&world, origin, end, |_, _| true).unwrap();
+            assert_eq!(hit.handle, handles[0]);
+            assert!(approx_eq(hit.fraction, 0.15));
+            assert!(approx_eq(hit.point.x, 1.5));
+            assert!(approx_eq(hit.normal.x, -1.0));
+
+            let hits = raycast_all(&world, origin, end, |_, _| true);
+            let order: Vec<BodyHandle> = hits.iter().map(|hit| hit.handle).collect();
+            assert_eq!(order, handles);
+            assert!(approx_eq(hits[3].point.x, 7.75));
+        }
+    }
+
+    #[test]
+    fn test_raycast_filter_and_miss() {
+        let (world, handles) = row(BroadPhase::dynamic_tree(0.1));
+
+        let hit = raycast(&world, Vector2D::new(0.0, 0.0), Vector2D::new(10.0, 0.0), |handle, _| handle != handles[0]).unwrap();
+        assert_eq!(hit.handle, handles[1]);
+        assert!(raycast(&world, Vector2D::new(0.0, 2.0), Vector2D::new(10.0, 2.0), |_, _| true).is_none());
+
+        // Straight down onto the tall polygon only.
+        let hit = raycast(&world, Vector2D::new(6.0, 5.0), Vector2D::new(6.0, -5.0), |_, _| true).unwrap();
+        assert_eq!(hit.handle, handles[2]);
+        assert!(approx_eq(hit.point.y, 1.0));
+        assert!(approx_eq(hit.normal.y, 1.0));
+    }
+
+    #[test]
+    fn test_shape_cast() {
+        for broad_phase in broad_phases() {
+            let (world, handles) = row(broad_phase);
+
+            let ball = Collider::circle(0.25).translated(Vector2D::new(3.8, 3.0));
+            let hit = shape_cast(&world, &ball, Vector2D::new(0.0, -6.0), |_, _| true).unwrap();
+            assert_eq!(hit.handle, handles[1]);
+            assert!(f32::abs(hit.fraction - 2.25 / 6.0) < 2e-3, "Hit at {}", hit.fraction);
+            assert!(approx_eq(hit.normal.y, 1.0));
+
+            let crate_shape = Collider::Box(BoundingBox::new(Vector2D::new(0.0, 0.0), 0.25, 0.25));
+            let hit = shape_cast(&world, &crate_shape, Vector2D::new(10.0, 0.0), |_, _| true).unwrap();
+            assert_eq!(hit.handle, handles[0]);
+            assert!(f32::abs(hit.fraction - 0.125) < 2e-3, "Hit at {}", hit.fraction);
+            assert!(approx_eq(hit.normal.x, -1.0));
+        }
+    }
+
+    #[test]
+    fn test_shape_cast_starting_inside() {
+        let (world, handles) = row(BroadPhase::BruteForce);
+        let ball = Collider::circle(0.25).translated(Vector2D::new(4.0, 0.6));
+
+        let hit = shape_cast(&world, &ball, Vector2D::new(5.0, 0.0), |_, _| true).unwrap();
+        assert_eq!(hit.handle, handles[1]);
+        assert_eq!(hit.fraction, 0.0);
+    }
+
+    #[test]
+    fn test_point_and_aabb_queries() {
+        for broad_phase in broad_phases() {
+            let (mut world, handles) = row(broad_phase);
+
+            assert_eq!(query_point(&world, Vector2D::new(6.2, 0.9), |_, _| true), vec![handles[2]]);
+            // Inside the circle's AABB but outside the circle.
+            assert!(query_point(&world, Vector2D::new(2.45, 0.45), |_, _| true).is_empty());
+
+            let aabb = BoundingBox::from_min_max(Vector2D::new(3.0, -0.2), Vector2D::new(7.0, 0.2));
+            assert_eq!(query_aabb(&world, &aabb, |_, _| true), vec![handles[1], handles[2]]);
+            assert_eq!(query_aabb(&world, &aabb, |_, body| body.collider.as_ref().is_some_and(|collider| matches!(collider, Collider::Box(_)))), vec![handles[1]]);
+
+            // Added after the last step, so the broad phase has not seen it yet.
+            let late = world.insert(static_body(Collider::circle(0.5), Vector2D::new(5.0, 0.0)));
+            assert_eq!(query_aabb(&world, &aabb, |_, _| true), vec![handles[1], handles[2], late]);
+            assert_eq!(query_point(&world, Vector2D::new(5.0, 0.0), |_, _| true), vec![late]);
+        }
+    }
+
+    #[test]
+    fn test_queries_find_bodies_moved_by_hand() {
+        for broad_phase in broad_phases() {
+            let (mut world, handles) = row(broad_phase.clone());
+            world.get_mut(handles[0]).unwrap().position = Vector2D::new(20.0, 0.0);
+            assert_eq!(query_point(&world, Vector2D::new(20.0, 0.0), |_, _| true), vec![handles[0]]);
+            assert_eq!(raycast(&world, Vector2D::new(20.0, 5.0), Vector2D::new(20.0, -5.0), |_, _| true).unwrap().handle, handles[0]);
+
+            let (mut world, handles) = row(broad_phase);
+            for (_, body) in world.iter_mut() {
+                body.position.y += 10.0;
+            }
+            assert_eq!(query_point(&world, Vector2D::new(4.0, 10.0), |_, _| true), vec![handles[1]]);
+            assert!(query_point(&world, Vector2D::new(4.0, 0.0), |_, _| true).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_queries_find_bodies_pushed_by_the_solver() {
+        for broad_phase in broad_phases() {
+            let mut world = World::with_gravity(Vector2D::new(0.0, 0.0)).with_broad_phase(broad_phase);
+            world.insert(static_body(Collider::circle(0.5), Vector2D::new(0.0, 0.0)));
+            let handle = world.insert(RigidBody::from_collider(Collider::circle(0.25), 1.0, Vector2D::new(0.3, 0.0)));
+            physics_step(&mut world, 1.0 / 60.0);
+
+            // Past where the ball's AABB was when the broad phase saw it.
+            let edge = world.get(handle).unwrap().position + Vector2D::new(0.24, 0.0);
+            assert!(edge.x > 0.55, "Only pushed to {:?}", edge);
+            assert_eq!(query_point(&world, edge, |_, _| true), vec![handle]);
+            let hit = raycast(&world, Vector2D::new(edge.x, 5.0), Vector2D::new(edge.x, -5.0), |_, _| true).unwrap();
+            assert_eq!(hit.handle, handle);
+        }
+    }
+
+    #[test]
+    fn test_queries_follow_a_new_broad_phase() {
+        let (mut world, handles) = row(BroadPhase::BruteForce);
+        world.set_broad_phase(BroadPhase::dynamic_tree(0.1));
+        assert_eq!(query_point(&world, Vector2D::new(2.0, 0.0), |_, _| true), vec![handles[0]]);
+        assert_eq!(raycast(&world, Vector2D::new(0.0, 0.0), Vector2D::new(10.0, 0.0), |_, _| true).unwrap().handle, handles[0]);
+    }
+
+    #[test]
+    fn test_very_long_ray() {
+        for broad_phase in broad_phases() {
+            let (world, handles) = row(broad_phase);
+            let hit = raycast(&world, Vector2D::new(0.0, 0.0), Vector2D::new(1e10, 0.0), |_, _| true).unwrap();
+            assert_eq!(hit.handle, handles[0]);
+        }
+    }
+}